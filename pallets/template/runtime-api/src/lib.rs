@@ -0,0 +1,36 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_marketplace::TokenView;
+
+sp_api::decl_runtime_apis! {
+	/// Read-only queries over the marketplace state
+	pub trait MarketplaceApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// Blocks since the token was listed, `None` if it isn't on sale
+		fn listing_age(token_id: u64) -> Option<BlockNumber>;
+		/// Tokens whose listing is older than `threshold` blocks
+		fn stale_listings(threshold: BlockNumber) -> Vec<u64>;
+		/// Whether the account was verified as a creator by root
+		fn is_verified_creator(account: AccountId) -> bool;
+		/// Boosted listings with the block their boost ends, soonest expiry first
+		fn featured_listings() -> Vec<(u64, BlockNumber)>;
+		/// Views of the given tokens in input order, at most MaxBatchSize of them
+		fn batch_token_query(token_ids: Vec<u64>) -> Vec<TokenView<AccountId, Balance>>;
+		/// Latest (token, buyer, seller, price, block) sales, oldest first
+		fn recent_sales() -> Vec<(u64, AccountId, AccountId, Balance, BlockNumber)>;
+		/// Owners of the given tokens in input order, at most MaxBatchSize of them
+		fn owners_of(token_ids: Vec<u64>) -> Vec<Option<AccountId>>;
+		/// Whether each of the given tokens exists, in input order, at most MaxBatchSize of them
+		fn exist(token_ids: Vec<u64>) -> Vec<bool>;
+		/// Accounts owning the most tokens, highest first. Scans every owner.
+		fn top_collectors(limit: u32) -> Vec<(AccountId, u64)>;
+		/// Accounts that minted the most tokens, highest first. Scans every creator.
+		fn top_creators(limit: u32) -> Vec<(AccountId, u64)>;
+	}
+}
@@ -2,13 +2,21 @@
 
 pub use pallet::*;
 
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
 #[frame_support::pallet]
 pub mod pallet {
 
 	use frame_support::{
 		pallet_prelude::*,
 		transactional,
-		traits::{Currency, tokens::ExistenceRequirement},
+		traits::{Currency, ReservableCurrency, BalanceStatus, Randomness, tokens::ExistenceRequirement},
+		sp_runtime::traits::{Hash, SaturatedConversion, Saturating, Zero},
+		sp_std::{cmp::Reverse, vec::Vec},
 	};
 	use frame_system::pallet_prelude::*;
 	use scale_info::TypeInfo;
@@ -16,26 +24,221 @@ pub mod pallet {
 	#[pallet::config]
 	pub trait Config: frame_system::Config {
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
-		type Currency: Currency<Self::AccountId>;
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// Maximum number of bid commitments a single sealed auction accepts
+		#[pallet::constant]
+		type MaxSealedBids: Get<u32>;
+
+		/// Blocks an installment may be late before the plan can be defaulted
+		#[pallet::constant]
+		type InstallmentGracePeriod: Get<Self::BlockNumber>;
+
+		/// Whether a defaulted buyer's payments go to the seller instead of back to the buyer
+		#[pallet::constant]
+		type ForfeitInstallmentsOnDefault: Get<bool>;
+
+		/// Maximum number of items handled by a single batch call
+		#[pallet::constant]
+		type MaxBatchSize: Get<u32>;
+
+		/// Account receiving marketplace fees
+		type FeeAccount: Get<Self::AccountId>;
+
+		/// Fee per block of featured placement bought with boost_listing
+		#[pallet::constant]
+		type BoostFee: Get<BalanceOf<Self>>;
+
+		/// Blocks an account must have held tokens before it can list one, zero disables the check
+		#[pallet::constant]
+		type MinAccountAgeToSell: Get<Self::BlockNumber>;
+
+		/// Account that keeps relinquished tokens for provenance
+		type NullAccount: Get<Self::AccountId>;
+
+		/// Whether relinquished tokens are burnt instead of moved to the null account
+		#[pallet::constant]
+		type BurnRelinquished: Get<bool>;
+
+		/// Number of sales kept in the RecentSales feed
+		#[pallet::constant]
+		type MaxRecentSales: Get<u32>;
+
+		/// Blocks a seller has to confirm a pending sale before the buyer can take the funds back
+		#[pallet::constant]
+		type SaleConfirmationTimeout: Get<Self::BlockNumber>;
+
+		/// Most value that can change hands in sales within one block, zero disables the cap
+		#[pallet::constant]
+		type MaxVolumePerBlock: Get<BalanceOf<Self>>;
+
+		/// Blocks after listing a token in which relisting it must change the price by MinRelistPriceDelta
+		#[pallet::constant]
+		type RelistWindow: Get<Self::BlockNumber>;
+
+		/// Smallest price change for a relisting within RelistWindow, zero disables the check
+		#[pallet::constant]
+		type MinRelistPriceDelta: Get<BalanceOf<Self>>;
+
+		/// Reports after which a listed token's trading is halted for review, zero disables it
+		#[pallet::constant]
+		type ReportThreshold: Get<u32>;
+
+		/// Blocks an automatic halt from reports lasts
+		#[pallet::constant]
+		type ReportHaltDuration: Get<Self::BlockNumber>;
+
+		/// Source of the seed each token gets at mint
+		type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
+
+		/// Maximum number of open buy commitments on a single listing
+		#[pallet::constant]
+		type MaxBuyCommitments: Get<u32>;
+
+		/// Maximum number of accounts on a single token's buyer allowlist
+		#[pallet::constant]
+		type MaxListingBuyers: Get<u32>;
+
+		/// Blocks a buy commitment can be revealed in before it lapses and frees its slot
+		#[pallet::constant]
+		type BuyCommitmentLifetime: Get<Self::BlockNumber>;
 	}
 
+	/// Version 1 added the listing fields to Order
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	type TokenID = u64;
 
+	type AuctionID = u64;
+
+	type PlanID = u64;
+
+	type ListingID = u128;
+
 	type BalanceOf<T> =
     	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
+	/// [TokenID, Buyer, Seller, Price, Block]
+	pub type SaleRecord<T> = (
+		TokenID,
+		<T as frame_system::Config>::AccountId,
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+	);
+
 	#[derive(Clone, Encode, Decode, MaxEncodedLen, TypeInfo)]
 	#[scale_info(skip_type_params(T))]
 	#[codec(mel_bound())]
 	pub struct Order<T: Config> {
 		pub token_id: TokenID,
 		pub sell_price: BalanceOf<T>,
+		/// Stays the same for the life of the listing, unlike its index in SellOrders
+		pub listing_id: ListingID,
+		pub listed_at: T::BlockNumber,
+		/// Buyers must commit_buy and then reveal_buy in a later block
+		pub require_commit: bool,
+		/// Listing is featured until this block
+		pub boosted_until: Option<T::BlockNumber>,
+		/// Purchases wait for the seller's confirm_sale instead of executing
+		pub require_confirmation: bool,
+		/// Only accounts in ListingBuyerAllowlist can buy
+		pub restrict_buyers: bool,
+	}
+
+	// Order as stored before storage version 1
+	#[derive(Decode)]
+	struct OldOrder<T: Config> {
+		token_id: TokenID,
+		sell_price: BalanceOf<T>,
+	}
+
+	#[derive(Clone, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct PendingSale<T: Config> {
+		pub buyer: T::AccountId,
+		/// Amount reserved from the buyer
+		pub price: BalanceOf<T>,
+		/// Last block in which the seller can confirm
+		pub expires_at: T::BlockNumber,
+	}
+
+	#[derive(Clone, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct BuyCommitment<T: Config> {
+		/// Hash of (buyer, token_id, price, salt)
+		pub hash: T::Hash,
+		/// Listing the commitment was made for
+		pub listing_id: ListingID,
+		pub committed_at: T::BlockNumber,
+	}
+
+	#[derive(Clone, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct SealedAuction<T: Config> {
+		pub token_id: TokenID,
+		pub seller: T::AccountId,
+		pub min_bid: BalanceOf<T>,
+		/// Last block in which bids can be committed
+		pub commit_end: T::BlockNumber,
+		/// Last block in which committed bids can be revealed
+		pub reveal_end: T::BlockNumber,
+		pub bid_count: u32,
+		/// Highest revealed bid so far
+		pub best_bid: Option<(T::AccountId, BalanceOf<T>)>,
+	}
+
+	#[derive(Clone, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct BidCommitment<T: Config> {
+		/// Hash of (bidder, amount, salt)
+		pub hash: T::Hash,
+		/// Reserved funds, the revealed amount cannot exceed this
+		pub deposit: BalanceOf<T>,
+	}
+
+	#[derive(Clone, Encode, Decode, MaxEncodedLen, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	pub struct InstallmentPlan<T: Config> {
+		pub token_id: TokenID,
+		pub seller: T::AccountId,
+		/// Only this account can pay the installments
+		pub buyer: T::AccountId,
+		pub total: BalanceOf<T>,
+		pub installments: u32,
+		pub interval: T::BlockNumber,
+		pub paid_installments: u32,
+		/// Sum of the installments paid so far, reserved on the buyer
+		pub paid: BalanceOf<T>,
+		/// Block by which the next installment is due
+		pub next_due: T::BlockNumber,
 	}
 
+	/// Everything a front end needs to render a token, returned by the runtime API
+	#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct TokenView<AccountId, Balance> {
+		pub token_id: u64,
+		/// `None` if the token doesn't exist
+		pub owner: Option<AccountId>,
+		/// Price of the active sell order, if any
+		pub sell_price: Option<Balance>,
+		pub in_auction: bool,
+		pub in_installment_plan: bool,
+		pub boosted: bool,
+	}
+
+	/// Id given to the next minted token. `u64::MAX` itself is never handed out:
+	/// the mint that would have to advance past it fails with `StorageOverflow`.
 	#[pallet::storage]
 	#[pallet::getter(fn get_next_token_id)]
 	pub type NextTokenId<T> = StorageValue<_, TokenID>;
@@ -52,6 +255,14 @@ pub mod pallet {
 	#[pallet::getter(fn get_sell_order)]
 	pub type SellOrders<T> = StorageMap<_, Blake2_128Concat, u128, Order<T>, OptionQuery>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn get_listing_nonce)]
+	pub type ListingNonce<T> = StorageValue<_, ListingID>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_listing_index)]
+	pub type ListingIdToIndex<T> = StorageMap<_, Blake2_128Concat, ListingID, u128, OptionQuery>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn is_onsale)]
 	pub type IsTokenOnSale<T> = StorageMap<_, Blake2_128Concat, TokenID, u128, OptionQuery>;
@@ -64,6 +275,121 @@ pub mod pallet {
 	#[pallet::getter(fn get_token_ids_of_owned_nfts)]
 	pub type OwnerToTokenIds<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, u64, TokenID, OptionQuery>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn get_listing_manager)]
+	pub type ListingManagers<T: Config> = StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_next_auction_id)]
+	pub type NextAuctionId<T> = StorageValue<_, AuctionID>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_sealed_auction)]
+	pub type SealedAuctions<T: Config> = StorageMap<_, Blake2_128Concat, AuctionID, SealedAuction<T>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_bid_commitment)]
+	pub type BidCommitments<T: Config> = StorageDoubleMap<_, Blake2_128Concat, AuctionID, Blake2_128Concat, T::AccountId, BidCommitment<T>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn is_in_auction)]
+	pub type TokenInAuction<T> = StorageMap<_, Blake2_128Concat, TokenID, AuctionID, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_next_plan_id)]
+	pub type NextPlanId<T> = StorageValue<_, PlanID>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_installment_plan)]
+	pub type InstallmentPlans<T: Config> = StorageMap<_, Blake2_128Concat, PlanID, InstallmentPlan<T>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn is_in_installment_plan)]
+	pub type TokenInInstallmentPlan<T> = StorageMap<_, Blake2_128Concat, TokenID, PlanID, OptionQuery>;
+
+	/// Open buy commitments on a token's current listing, pruned when the listing ends.
+	/// Lapsed ones are also pruned once the listing runs out of slots.
+	#[pallet::storage]
+	#[pallet::getter(fn get_buy_commitment)]
+	pub type BuyCommitments<T: Config> = StorageDoubleMap<_, Blake2_128Concat, TokenID, Blake2_128Concat, T::AccountId, BuyCommitment<T>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_buy_commitment_count)]
+	pub type BuyCommitmentCount<T> = StorageMap<_, Blake2_128Concat, TokenID, u32, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_verified_creator)]
+	pub type VerifiedCreators<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	/// Block of each account's latest successful signed call into the marketplace
+	#[pallet::storage]
+	#[pallet::getter(fn get_last_active)]
+	pub type LastActive<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
+
+	/// Block in which an account first received a token
+	#[pallet::storage]
+	#[pallet::getter(fn get_first_seen)]
+	pub type FirstSeen<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
+
+	/// Latest sales, oldest first. The oldest entry is dropped once the feed is full.
+	#[pallet::storage]
+	#[pallet::getter(fn get_recent_sales)]
+	pub type RecentSales<T: Config> = StorageValue<_, BoundedVec<SaleRecord<T>, T::MaxRecentSales>, ValueQuery>;
+
+	/// Purchase of a listed token waiting for the seller's confirmation
+	#[pallet::storage]
+	#[pallet::getter(fn get_pending_sale)]
+	pub type PendingSales<T: Config> = StorageMap<_, Blake2_128Concat, TokenID, PendingSale<T>, OptionQuery>;
+
+	/// Last block of a temporary trading halt on a token
+	#[pallet::storage]
+	#[pallet::getter(fn get_halted_until)]
+	pub type TokenHaltedUntil<T: Config> = StorageMap<_, Blake2_128Concat, TokenID, T::BlockNumber, OptionQuery>;
+
+	/// Value of the sales made so far in the current block, cleared in on_initialize
+	#[pallet::storage]
+	#[pallet::getter(fn get_block_volume)]
+	pub type BlockVolume<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+	/// Account paid the proceeds of a token's sale instead of its owner. Cleared when the token moves.
+	#[pallet::storage]
+	#[pallet::getter(fn get_proceeds_recipient)]
+	pub type ProceedsRecipient<T: Config> = StorageMap<_, Blake2_128Concat, TokenID, T::AccountId, OptionQuery>;
+
+	/// Number of tokens each account has minted
+	#[pallet::storage]
+	#[pallet::getter(fn get_creator_token_count)]
+	pub type CreatorTokenCount<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, u64, OptionQuery>;
+
+	/// Price and block of a token's latest listing by its current owner
+	#[pallet::storage]
+	#[pallet::getter(fn get_previous_listing)]
+	pub type PreviousListing<T: Config> = StorageMap<_, Blake2_128Concat, TokenID, (BalanceOf<T>, T::BlockNumber), OptionQuery>;
+
+	/// Buyers allowed to buy a token whose listing restricts buyers. Cleared when the token moves.
+	#[pallet::storage]
+	#[pallet::getter(fn get_listing_buyer)]
+	pub type ListingBuyerAllowlist<T: Config> = StorageDoubleMap<_, Blake2_128Concat, TokenID, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn get_listing_buyer_count)]
+	pub type ListingBuyerCount<T> = StorageMap<_, Blake2_128Concat, TokenID, u32, ValueQuery>;
+
+	/// Number of accounts that reported a token under its current owner since its last halt
+	#[pallet::storage]
+	#[pallet::getter(fn get_report_count)]
+	pub type Reports<T: Config> = StorageMap<_, Blake2_128Concat, TokenID, u32, ValueQuery>;
+
+	/// Accounts that reported a token, each counts once
+	#[pallet::storage]
+	#[pallet::getter(fn get_reporter)]
+	pub type Reporters<T: Config> = StorageDoubleMap<_, Blake2_128Concat, TokenID, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	/// Seed drawn for a token at mint, for front ends to derive generative traits from
+	#[pallet::storage]
+	#[pallet::getter(fn get_token_seed)]
+	pub type TokenSeed<T: Config> = StorageMap<_, Blake2_128Concat, TokenID, T::Hash, OptionQuery>;
+
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
@@ -75,6 +401,72 @@ pub mod pallet {
 		CancelledOrder(TokenID),
 		/// [Buyer, Seller, Price]
 		NFTSold(T::AccountId, T::AccountId, BalanceOf<T>),
+		/// [Owner, Manager, Enabled]
+		ListingManagerSet(T::AccountId, T::AccountId, bool),
+		/// [AuctionID, TokenID, Seller]
+		SealedAuctionStarted(AuctionID, TokenID, T::AccountId),
+		/// [AuctionID, Bidder, Deposit]
+		BidCommitted(AuctionID, T::AccountId, BalanceOf<T>),
+		/// [AuctionID, Bidder, Amount]
+		BidRevealed(AuctionID, T::AccountId, BalanceOf<T>),
+		/// [AuctionID, Winner, Price]
+		SealedAuctionSettled(AuctionID, Option<T::AccountId>, BalanceOf<T>),
+		/// [PlanID, TokenID, Seller, Buyer, Total]
+		InstallmentPlanStarted(PlanID, TokenID, T::AccountId, T::AccountId, BalanceOf<T>),
+		/// [PlanID, Buyer, Amount]
+		InstallmentPaid(PlanID, T::AccountId, BalanceOf<T>),
+		/// [PlanID, Buyer, Seller]
+		InstallmentPlanCompleted(PlanID, T::AccountId, T::AccountId),
+		/// [PlanID, Buyer, Paid, Forfeited]
+		InstallmentPlanDefaulted(PlanID, T::AccountId, BalanceOf<T>, bool),
+		/// [PlanID]
+		InstallmentPlanCancelled(PlanID),
+		/// [Owner, NumberOfNFTs]
+		OwnerReindexed(T::AccountId, u64),
+		/// [TokenID, Required]
+		CommitRequirementSet(TokenID, bool),
+		/// [TokenID, Buyer]
+		BuyCommitted(TokenID, T::AccountId),
+		/// [TokenID, Price]
+		PriceUpdated(TokenID, BalanceOf<T>),
+		/// [Creator]
+		CreatorVerified(T::AccountId),
+		/// [Creator]
+		CreatorUnverified(T::AccountId),
+		/// [TokenID, BoostedUntil, Fee]
+		ListingBoosted(TokenID, T::BlockNumber, BalanceOf<T>),
+		/// [TokenID, Redeemer, ShippingInfoHash]
+		PhysicalRedeemed(TokenID, T::AccountId, T::Hash),
+		/// [Seller, NumberOfCancelledOrders]
+		InactiveSellerDelisted(T::AccountId, u64),
+		/// [TokenID, FormerOwner]
+		TokenRelinquished(TokenID, T::AccountId),
+		/// [TokenID, Required]
+		ConfirmationRequirementSet(TokenID, bool),
+		/// [TokenID, Buyer, Price, ExpiresAt]
+		SaleAwaitingConfirmation(TokenID, T::AccountId, BalanceOf<T>, T::BlockNumber),
+		/// [TokenID, Buyer, Price]
+		SaleConfirmed(TokenID, T::AccountId, BalanceOf<T>),
+		/// [TokenID, Buyer, Price]
+		PendingSaleRefunded(TokenID, T::AccountId, BalanceOf<T>),
+		/// [TokenID, HaltedUntil]
+		TokenTradingHalted(TokenID, T::BlockNumber),
+		/// [TokenID, Recipient]
+		ProceedsRecipientSet(TokenID, Option<T::AccountId>),
+		/// [TokenID, Seller, Refund]
+		BoostRefunded(TokenID, T::AccountId, BalanceOf<T>),
+		/// [Account, NumberOfReleasedBidsPurchasesAndPlans, TotalUnreserved]
+		AccountReservesReleased(T::AccountId, u32, BalanceOf<T>),
+		/// [NumberOfImportedTokens]
+		TokensImported(u32),
+		/// [TokenID, Restricted]
+		BuyerRestrictionSet(TokenID, bool),
+		/// [TokenID, Buyer]
+		ListingBuyerAdded(TokenID, T::AccountId),
+		/// [TokenID, Buyer]
+		ListingBuyerRemoved(TokenID, T::AccountId),
+		/// [TokenID, Reporter, ReasonCode]
+		ListingReported(TokenID, T::AccountId, u8),
 	}
 
 	#[pallet::error]
@@ -97,39 +489,214 @@ pub mod pallet {
 		NoSellOrdersFound,
 		/// Insufficient fund to purchase NFT
 		NotEnoughBalance,
+		/// You are neither the owner nor a listing manager of the owner
+		NotListingManager,
+		/// Token is being auctioned
+		TokenInAuction,
+		/// Auction doesn't exist for the given auctionID
+		InvalidAuctionID,
+		/// Commit and reveal periods must be at least one block
+		InvalidAuctionPeriod,
+		/// Bids can no longer be committed for this auction
+		CommitPhaseOver,
+		/// Bids can only be revealed after the commit phase ended
+		NotInRevealPhase,
+		/// The reveal phase of this auction hasn't ended yet
+		AuctionNotFinished,
+		/// You already committed a bid to this auction
+		BidAlreadyCommitted,
+		/// No bid commitment found for you in this auction
+		BidCommitmentNotFound,
+		/// Revealed amount and salt don't match the commitment
+		InvalidBidReveal,
+		/// Revealed amount is below the minimum bid or above the deposit
+		InvalidBidAmount,
+		/// Auction reached the maximum number of bids
+		TooManyBids,
+		/// Sellers cannot bid on their own auction
+		SellerCannotBid,
+		/// Token is being sold in installments
+		TokenInInstallmentPlan,
+		/// Installment plans need a nonzero interval and a total of at least one unit per installment
+		InvalidInstallmentPlan,
+		/// Installment plan doesn't exist for the given planID
+		InstallmentPlanNotFound,
+		/// You are not the buyer of this installment plan
+		NotInstallmentBuyer,
+		/// The installment is past its grace period
+		InstallmentOverdue,
+		/// The buyer is not late on this installment plan
+		InstallmentNotOverdue,
+		/// The buyer already paid an installment of this plan
+		InstallmentPlanAlreadyStarted,
+		/// This listing must be bought through commit_buy and reveal_buy
+		CommitRequired,
+		/// This listing doesn't take buy commitments
+		CommitNotRequired,
+		/// No buy commitment found for you on this listing
+		BuyCommitmentNotFound,
+		/// A buy commitment can only be revealed in a later block
+		RevealTooEarly,
+		/// Revealed price and salt don't match the commitment
+		InvalidBuyReveal,
+		/// The listing's price changed since the buy commitment was made
+		ListingPriceChanged,
+		/// The listing reached the maximum number of buy commitments
+		TooManyBuyCommitments,
+		/// Too many items in a single batch call
+		BatchTooLarge,
+		/// Boosts must last at least one block
+		InvalidBoostDuration,
+		/// The account was active within the inactivity threshold
+		SellerStillActive,
+		/// The token owner's account is too new to sell
+		AccountTooNew,
+		/// Another purchase of the token is waiting for confirmation
+		SaleAlreadyPending,
+		/// No pending sale of the token for this buyer
+		PendingSaleNotFound,
+		/// The seller didn't confirm in time
+		PendingSaleExpired,
+		/// The seller can still confirm the pending sale
+		PendingSaleNotExpired,
+		/// Trading of the token is halted for now
+		TokenTradingHalted,
+		/// The sale would go past this block's trade volume cap, try again next block
+		BlockVolumeCapReached,
+		/// The token was listed recently at almost the same price
+		RelistPriceTooSimilar,
+		/// A token with this id already exists
+		TokenAlreadyExists,
+		/// The listing is restricted to allowlisted buyers
+		BuyerNotAllowed,
+		/// The token's buyer allowlist is full
+		TooManyListingBuyers,
+		/// The account already reported this token
+		AlreadyReported,
+		/// Listing reports are disabled
+		ReportingDisabled,
+		/// The witness is lower than the number of entries the call has to go through
+		WitnessTooLow,
+		/// The reserved funds no longer cover the price
+		ReserveTooLow,
+		/// The buy commitment lapsed, commit again
+		BuyCommitmentExpired,
+	}
+
+	// Upper bound of destroy_sell_order, including the refund of a pending sale
+	// and clearing the listing's commitments and reports
+	fn destroy_sell_order_weight<T: Config>() -> Weight {
+		T::DbWeight::get().reads_writes(
+			5,
+			11 + T::MaxBuyCommitments::get() as u64
+		)
+	}
+
+	// Upper bound of transfer_ownership for a token that is no longer listed
+	fn transfer_ownership_weight<T: Config>() -> Weight {
+		T::DbWeight::get().reads_writes(6, 11 + T::MaxListingBuyers::get() as u64 + T::ReportThreshold::get() as u64)
+	}
+
+	// Upper bound of burn_token, which clears every per-token map
+	fn burn_token_weight<T: Config>() -> Weight {
+		T::DbWeight::get().reads_writes(
+			6,
+			12 + T::MaxListingBuyers::get() as u64 + T::MaxBuyCommitments::get() as u64 + T::ReportThreshold::get() as u64
+		)
+	}
+
+	// Upper bound of do_buy: paying the seller, refunding a boost, closing the listing
+	// and moving the token
+	fn do_buy_weight<T: Config>() -> Weight {
+		T::DbWeight::get().reads_writes(11, 6)
+			.saturating_add(destroy_sell_order_weight::<T>())
+			.saturating_add(transfer_ownership_weight::<T>())
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			BlockVolume::<T>::kill();
+			T::DbWeight::get().writes(1)
+		}
+
+		// Existing orders get their index as listing id, no listing flags and the upgrade
+		// block as listing time, since the old ones don't say when they were listed
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() >= STORAGE_VERSION {
+				return T::DbWeight::get().reads(1);
+			}
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let mut migrated: u64 = 0;
+			SellOrders::<T>::translate::<OldOrder<T>, _>(|index, old| {
+				migrated += 1;
+				ListingIdToIndex::<T>::insert(index, index);
+				Some(Order {
+					token_id: old.token_id,
+					sell_price: old.sell_price,
+					listing_id: index,
+					listed_at: now,
+					require_commit: false,
+					boosted_until: None,
+					require_confirmation: false,
+					restrict_buyers: false,
+				})
+			});
+
+			ListingNonce::<T>::put(Self::get_number_of_sell_orders().unwrap_or(0));
+			STORAGE_VERSION.put::<Pallet<T>>();
+
+			T::DbWeight::get().reads_writes(2 + migrated, 2 + 2 * migrated)
+		}
 	}
 
+	// Calls are encoded by their position: new calls go after buy, so the original four
+	// keep their indices and transactions signed against them still decode
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
 		/// Mints a NFT
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,4))]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5,8))]
 		pub fn mint(_origin: OriginFor<T>) -> DispatchResult {
 			// Check that the extrinsic was signed and get the signer.
 			let owner = ensure_signed(_origin)?;
 
-			// Gets token_id and updates NextTokenId
+			// Gets token_id and the id that follows it. Both counters are checked
+			// before anything is written so an overflow leaves storage untouched.
 			let token_id: TokenID = Self::get_next_token_id().unwrap_or(0);
-			<NextTokenId<T>>::put(token_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?);
+			let next_token_id = token_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
 
 			// Gets index of the current nfts for the owner
 			let number_of_nfts = Self::get_number_of_nfts_owned(&owner).unwrap_or(0);
+			let new_number_of_nfts = number_of_nfts.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
+
+			<NextTokenId<T>>::put(next_token_id);
 			<OwnerToNumberOfNFTs<T>>::insert(
 				&owner,
-				number_of_nfts + 1
+				new_number_of_nfts
 			);
 
 			// Adds record of tokenIds owner
 			TokenIdToOwner::<T>::insert(&token_id, (&owner, &number_of_nfts));
+			Self::note_first_seen(&owner);
 
 			// Adds tokenId to owners list of owned tokenIds
 			OwnerToTokenIds::<T>::insert(&owner, &number_of_nfts, &token_id);
 
+			// Can't overflow before NextTokenId does
+			CreatorTokenCount::<T>::mutate(&owner, |count| *count = Some(count.unwrap_or(0).saturating_add(1)));
+
+			// The token id in the subject keeps seeds of tokens minted in the same block apart
+			let (seed, _) = T::Randomness::random(&(b"nft/seed", token_id).encode());
+			TokenSeed::<T>::insert(&token_id, seed);
+
+			Self::note_activity(&owner);
 			Self::deposit_event(Event::NFTMinted(token_id, owner));
 			Ok(())
 		}
 
 		/// Sell NFT
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,3))]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(10,8))]
 		pub fn sell(_origin: OriginFor<T>, _token_id: TokenID, _price: BalanceOf<T>) -> DispatchResult {
 
 			// Check that the extrinsic was signed and get the signer.
@@ -141,12 +708,43 @@ pub mod pallet {
 				None => Err(<Error<T>>::InvalidTokenID)?
 			};
 
-			ensure!(who == token_owner, Error::<T>::NotTokenOwner);
+			Self::ensure_listing_authority(&who, &token_owner)?;
 			ensure!(!IsTokenOnSale::<T>::contains_key(&_token_id), Error::<T>::TokenAlreadyOnSale);
+			Self::ensure_not_halted(_token_id)?;
+
+			// Accounts holding tokens since before FirstSeen was tracked count as old
+			let first_seen = Self::get_first_seen(&token_owner).unwrap_or_else(Zero::zero);
+			ensure!(
+				<frame_system::Pallet<T>>::block_number().saturating_sub(first_seen) >= T::MinAccountAgeToSell::get(),
+				Error::<T>::AccountTooNew
+			);
+			ensure!(!TokenInAuction::<T>::contains_key(&_token_id), Error::<T>::TokenInAuction);
+			ensure!(!TokenInInstallmentPlan::<T>::contains_key(&_token_id), Error::<T>::TokenInInstallmentPlan);
+
+			// Relisting only to look recent has to move the price
+			let now = <frame_system::Pallet<T>>::block_number();
+			if let Some((previous_price, listed_at)) = Self::get_previous_listing(&_token_id) {
+				let delta = T::MinRelistPriceDelta::get();
+				ensure!(
+					delta.is_zero() ||
+						now.saturating_sub(listed_at) >= T::RelistWindow::get() ||
+						_price.saturating_sub(previous_price).max(previous_price.saturating_sub(_price)) >= delta,
+					Error::<T>::RelistPriceTooSimilar
+				);
+			}
+
+			let listing_id: ListingID = Self::get_listing_nonce().unwrap_or(0);
+			let next_listing_nonce = listing_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
 
 			let new_order = Order {
 				token_id: _token_id,
 				sell_price: _price,
+				listing_id,
+				listed_at: now,
+				require_commit: false,
+				boosted_until: None,
+				require_confirmation: false,
+				restrict_buyers: false,
 			};
 
 			let number_of_sell_orders = Self::get_number_of_sell_orders().unwrap_or(0);
@@ -156,16 +754,20 @@ pub mod pallet {
 					ok_or(Error::<T>::StorageOverflow)?
 			);
 
+			ListingNonce::<T>::put(next_listing_nonce);
 			SellOrders::<T>::insert(&number_of_sell_orders, &new_order);
 			IsTokenOnSale::<T>::insert(&_token_id, &number_of_sell_orders);
+			ListingIdToIndex::<T>::insert(&listing_id, &number_of_sell_orders);
+			PreviousListing::<T>::insert(&_token_id, (_price, now));
 
+			Self::note_listing_activity(&who, &token_owner);
 			Self::deposit_event(Event::SellOrderCreated(_token_id, _price));
 
 			Ok(())
 		}
 
 		// Cancel a sell order
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5,6))]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,2) + destroy_sell_order_weight::<T>())]
 		pub fn cancel_order(_origin: OriginFor<T>, _token_id: u64) -> DispatchResult {
 			// Check that the extrinsic was signed and get the signer.
 			let who = ensure_signed(_origin)?;
@@ -176,8 +778,8 @@ pub mod pallet {
 				None => Err(<Error<T>>::InvalidTokenID)?
 			};
 
-			// Check if who is the owner of the token
-			ensure!(who == token_owner, Error::<T>::NotTokenOwner);
+			// Check if who is the owner of the token or one of its listing managers
+			Self::ensure_listing_authority(&who, &token_owner)?;
 
 			// Get the index of the order in SellOrders
 			let index_in_sell_orders = match Self::is_onsale(&_token_id) {
@@ -186,12 +788,13 @@ pub mod pallet {
 			};
 
 			Self::destroy_sell_order(index_in_sell_orders)?;
+			Self::note_listing_activity(&who, &token_owner);
 			Self::deposit_event(Event::CancelledOrder(_token_id));
 
 			Ok(())
 		}
 
-		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(9,11))]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,1) + do_buy_weight::<T>())]
 		#[transactional]
 		pub fn buy(_origin: OriginFor<T>, _token_id: TokenID) -> DispatchResult {
 			let buyer = ensure_signed(_origin)?;
@@ -201,73 +804,1419 @@ pub mod pallet {
 				None => Err(<Error<T>>::TokenNotOnSale)?
 			};
 
-			let (seller, idx) = Self::get_nft_details(_token_id).unwrap();
-			let sell_price = Self::get_sell_order(sell_id).unwrap().sell_price;
-			
-			// Transfer balance
-			ensure!(T::Currency::free_balance(&buyer) >= sell_price, <Error<T>>::NotEnoughBalance);
-			T::Currency::transfer(&buyer, &seller, sell_price, ExistenceRequirement::KeepAlive)?;
+			let order = Self::get_sell_order(sell_id).ok_or(Error::<T>::SellOrderNotFound)?;
+			ensure!(!order.require_commit, Error::<T>::CommitRequired);
 
-			// Delete sell order
-			Self::destroy_sell_order(sell_id)?;
+			Self::do_buy(buyer.clone(), _token_id, sell_id)?;
+			Self::note_activity(&buyer);
+			Ok(())
+		}
+
+		/// Allow or disallow an account to create and cancel listings on behalf of the caller.
+		/// Managers cannot move or buy the caller's tokens.
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(2))]
+		pub fn set_listing_manager(_origin: OriginFor<T>, _manager: T::AccountId, _enabled: bool) -> DispatchResult {
+			let owner = ensure_signed(_origin)?;
 
-			// Remove seller as the owner of the NFT
-			let seller_nft_count = Self::get_number_of_nfts_owned(&seller).unwrap();
+			if _enabled {
+				ListingManagers::<T>::insert(&owner, &_manager, ());
+			} else {
+				ListingManagers::<T>::remove(&owner, &_manager);
+			}
 
-			<OwnerToNumberOfNFTs<T>>::insert(
-				&seller,
-				seller_nft_count - 1
-			);
+			Self::note_activity(&owner);
+			Self::deposit_event(Event::ListingManagerSet(owner, _manager, _enabled));
+			Ok(())
+		}
+
+		/// Reprice several listings at once. Every token must be listed and owned or managed
+		/// by the caller, otherwise none of the prices change.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			4 * _updates.len() as u64,
+			1 + 2 * _updates.len() as u64
+		))]
+		#[transactional]
+		pub fn batch_update_prices(_origin: OriginFor<T>, _updates: Vec<(TokenID, BalanceOf<T>)>) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			ensure!(_updates.len() <= T::MaxBatchSize::get() as usize, Error::<T>::BatchTooLarge);
 
-			if idx != (seller_nft_count - 1) {
-				let last_nft_id = Self::get_token_ids_of_owned_nfts(&seller, seller_nft_count - 1).unwrap();
-				OwnerToTokenIds::<T>::insert(&seller, idx, last_nft_id);
+			for (token_id, price) in _updates {
+				Self::mutate_listing(&who, token_id, |order| order.sell_price = price)?;
+
+				Self::deposit_event(Event::PriceUpdated(token_id, price));
 			}
-			OwnerToTokenIds::<T>::remove(&seller, seller_nft_count-1);
 
-			// Make buyer the owner of the NFT
-			let buyer_nft_count = Self::get_number_of_nfts_owned(&buyer).unwrap_or(0);
+			Self::note_activity(&who);
+			Ok(())
+		}
+
+		/// Feature a listing for `_duration` blocks, paying BoostFee per block to the fee account.
+		/// Boosting a listing that is still featured extends its current boost.
+		/// Only the owner can boost, since the unused fee is refunded to them.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5,4))]
+		#[transactional]
+		pub fn boost_listing(_origin: OriginFor<T>, _token_id: TokenID, _duration: T::BlockNumber) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			// Listing managers don't get to spend the owner's money, or theirs on the owner
+			ensure!(who == token_owner, Error::<T>::NotTokenOwner);
+			ensure!(!_duration.is_zero(), Error::<T>::InvalidBoostDuration);
+
+			let sell_id = match Self::is_onsale(&_token_id) {
+				Some(id) => id,
+				None => Err(<Error<T>>::TokenNotOnSale)?
+			};
 
-			TokenIdToOwner::<T>::insert(_token_id, (&buyer, &buyer_nft_count));
+			let blocks: u128 = _duration.saturated_into();
+			let fee = T::BoostFee::get().saturating_mul(blocks.saturated_into());
+			T::Currency::transfer(&who, &T::FeeAccount::get(), fee, ExistenceRequirement::KeepAlive)?;
 
-			<OwnerToNumberOfNFTs<T>>::insert(
-				&buyer,
-				buyer_nft_count.checked_add(1).ok_or(Error::<T>::StorageOverflow)?
+			let now = <frame_system::Pallet<T>>::block_number();
+			let boosted_until = SellOrders::<T>::try_mutate(&sell_id, |order| -> Result<T::BlockNumber, Error<T>> {
+				let order = order.as_mut().ok_or(Error::<T>::SellOrderNotFound)?;
+				let boost_start = match order.boosted_until {
+					Some(until) if until > now => until,
+					_ => now,
+				};
+				let boosted_until = boost_start.saturating_add(_duration);
+				order.boosted_until = Some(boosted_until);
+				Ok(boosted_until)
+			})?;
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::ListingBoosted(_token_id, boosted_until, fee));
+			Ok(())
+		}
+
+		/// Require buyers of a listing to commit to the purchase before revealing it,
+		/// so a pending buy cannot be copied and front-run
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,3))]
+		pub fn set_require_commit(_origin: OriginFor<T>, _token_id: TokenID, _required: bool) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			Self::mutate_listing(&who, _token_id, |order| order.require_commit = _required)?;
+
+			Self::deposit_event(Event::CommitRequirementSet(_token_id, _required));
+			Ok(())
+		}
+
+		/// Commit to buying a listing that requires it. `_hash` is the hash of
+		/// (buyer, token_id, price, salt), the purchase happens in reveal_buy.
+		/// A full listing makes room by dropping its lapsed commitments.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			4 + T::MaxBuyCommitments::get() as u64,
+			3 + T::MaxBuyCommitments::get() as u64
+		))]
+		pub fn commit_buy(_origin: OriginFor<T>, _token_id: TokenID, _hash: T::Hash) -> DispatchResult {
+			let buyer = ensure_signed(_origin)?;
+
+			let sell_id = match Self::is_onsale(&_token_id) {
+				Some(id) => id,
+				None => Err(<Error<T>>::TokenNotOnSale)?
+			};
+
+			let order = Self::get_sell_order(sell_id).ok_or(Error::<T>::SellOrderNotFound)?;
+			ensure!(order.require_commit, Error::<T>::CommitNotRequired);
+
+			// Committing again replaces the buyer's earlier commitment
+			let now = <frame_system::Pallet<T>>::block_number();
+			if !BuyCommitments::<T>::contains_key(&_token_id, &buyer) {
+				let mut count = Self::get_buy_commitment_count(&_token_id);
+				if count >= T::MaxBuyCommitments::get() {
+					count = Self::prune_lapsed_buy_commitments(_token_id, now);
+				}
+				ensure!(count < T::MaxBuyCommitments::get(), Error::<T>::TooManyBuyCommitments);
+				BuyCommitmentCount::<T>::insert(&_token_id, count + 1);
+			}
+
+			let commitment = BuyCommitment {
+				hash: _hash,
+				listing_id: order.listing_id,
+				committed_at: now,
+			};
+			BuyCommitments::<T>::insert(&_token_id, &buyer, &commitment);
+
+			Self::note_activity(&buyer);
+			Self::deposit_event(Event::BuyCommitted(_token_id, buyer));
+			Ok(())
+		}
+
+		/// Reveal a buy commitment made in an earlier block, at most BuyCommitmentLifetime blocks
+		/// ago, and buy the listing at the committed price. Fails if the price changed in the meantime.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3,3) + do_buy_weight::<T>())]
+		#[transactional]
+		pub fn reveal_buy(
+			_origin: OriginFor<T>,
+			_token_id: TokenID,
+			_price: BalanceOf<T>,
+			_salt: [u8; 32],
+		) -> DispatchResult {
+			let buyer = ensure_signed(_origin)?;
+
+			let sell_id = match Self::is_onsale(&_token_id) {
+				Some(id) => id,
+				None => Err(<Error<T>>::TokenNotOnSale)?
+			};
+
+			let order = Self::get_sell_order(sell_id).ok_or(Error::<T>::SellOrderNotFound)?;
+			let commitment = Self::get_buy_commitment(&_token_id, &buyer).ok_or(Error::<T>::BuyCommitmentNotFound)?;
+
+			// Commitments made for an earlier listing of the same token don't count
+			ensure!(commitment.listing_id == order.listing_id, Error::<T>::BuyCommitmentNotFound);
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(now > commitment.committed_at, Error::<T>::RevealTooEarly);
+			ensure!(!Self::buy_commitment_lapsed(&commitment, now), Error::<T>::BuyCommitmentExpired);
+			ensure!(
+				T::Hashing::hash_of(&(&buyer, _token_id, _price, _salt)) == commitment.hash,
+				Error::<T>::InvalidBuyReveal
 			);
+			// A reprice after the commitment must not make the buyer pay more
+			ensure!(order.sell_price == _price, Error::<T>::ListingPriceChanged);
 
-			OwnerToTokenIds::<T>::insert(&buyer, &buyer_nft_count, &_token_id);
+			BuyCommitments::<T>::remove(&_token_id, &buyer);
+			BuyCommitmentCount::<T>::mutate(&_token_id, |count| *count = count.saturating_sub(1));
 
-			Self::deposit_event(Event::NFTSold(buyer, seller, sell_price));
+			Self::do_buy(buyer.clone(), _token_id, sell_id)?;
+			Self::note_activity(&buyer);
 			Ok(())
 		}
-	}
 
-	impl<T: Config> Pallet<T> {
-		
-		fn destroy_sell_order(index_in_sell_orders: u128) -> Result<(), Error<T>> {
+		/// Make purchases of a listing wait for the seller to confirm them
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,3))]
+		pub fn set_require_confirmation(_origin: OriginFor<T>, _token_id: TokenID, _required: bool) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
 
-			let token_id: TokenID = Self::get_sell_order(index_in_sell_orders).unwrap().token_id;
+			Self::mutate_listing(&who, _token_id, |order| order.require_confirmation = _required)?;
 
-			// Get the index of the last order in SellOrders
-			let last_index_in_sell_orders = Self::get_number_of_sell_orders().unwrap() - 1;
+			Self::deposit_event(Event::ConfirmationRequirementSet(_token_id, _required));
+			Ok(())
+		}
 
-			if index_in_sell_orders != last_index_in_sell_orders {
+		/// Restrict a listing to the buyers in the token's allowlist
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,3))]
+		pub fn set_restrict_buyers(_origin: OriginFor<T>, _token_id: TokenID, _restricted: bool) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
 
-				// Order at the last index
-				let order_at_last_index = match Self::get_sell_order(&last_index_in_sell_orders) {
-					Some(order) => order,
-					None => Err(<Error<T>>::SellOrderNotFound)?
-				};
+			Self::mutate_listing(&who, _token_id, |order| order.restrict_buyers = _restricted)?;
 
-				let token_id_of_last_order = order_at_last_index.token_id;
-				
-				// Insert last order at index of deleted order
-				SellOrders::<T>::insert(&index_in_sell_orders, &order_at_last_index);
-				IsTokenOnSale::<T>::insert(&token_id_of_last_order, &index_in_sell_orders);
+			Self::deposit_event(Event::BuyerRestrictionSet(_token_id, _restricted));
+			Ok(())
+		}
+
+		/// Allow an account to buy the token when its listing restricts buyers
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,4))]
+		pub fn add_listing_buyer(_origin: OriginFor<T>, _token_id: TokenID, _buyer: T::AccountId) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			Self::ensure_listing_authority(&who, &token_owner)?;
+
+			if !ListingBuyerAllowlist::<T>::contains_key(&_token_id, &_buyer) {
+				let count = Self::get_listing_buyer_count(&_token_id);
+				ensure!(count < T::MaxListingBuyers::get(), Error::<T>::TooManyListingBuyers);
+				ListingBuyerCount::<T>::insert(&_token_id, count + 1);
+				ListingBuyerAllowlist::<T>::insert(&_token_id, &_buyer, ());
+			}
+
+			Self::note_listing_activity(&who, &token_owner);
+			Self::deposit_event(Event::ListingBuyerAdded(_token_id, _buyer));
+			Ok(())
+		}
+
+		/// Take an account off the token's buyer allowlist
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,4))]
+		pub fn remove_listing_buyer(_origin: OriginFor<T>, _token_id: TokenID, _buyer: T::AccountId) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			Self::ensure_listing_authority(&who, &token_owner)?;
+
+			if ListingBuyerAllowlist::<T>::take(&_token_id, &_buyer).is_some() {
+				ListingBuyerCount::<T>::mutate(&_token_id, |count| *count = count.saturating_sub(1));
 			}
 
+			Self::note_listing_activity(&who, &token_owner);
+			Self::deposit_event(Event::ListingBuyerRemoved(_token_id, _buyer));
+			Ok(())
+		}
+
+		/// Confirm a pending sale, paying the seller from the buyer's reserve
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(12,8)
+				+ destroy_sell_order_weight::<T>()
+				+ transfer_ownership_weight::<T>()
+		)]
+		#[transactional]
+		pub fn confirm_sale(_origin: OriginFor<T>, _token_id: TokenID, _buyer: T::AccountId) -> DispatchResult {
+			let seller = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			// Listing managers can't move tokens, so only the owner confirms
+			ensure!(seller == token_owner, Error::<T>::NotTokenOwner);
+			Self::ensure_not_halted(_token_id)?;
+
+			let sell_id = match Self::is_onsale(&_token_id) {
+				Some(id) => id,
+				None => Err(<Error<T>>::TokenNotOnSale)?
+			};
+
+			let order = Self::get_sell_order(sell_id).ok_or(Error::<T>::SellOrderNotFound)?;
+			let pending = Self::get_pending_sale(&_token_id).ok_or(Error::<T>::PendingSaleNotFound)?;
+			ensure!(pending.buyer == _buyer, Error::<T>::PendingSaleNotFound);
+			ensure!(<frame_system::Pallet<T>>::block_number() <= pending.expires_at, Error::<T>::PendingSaleExpired);
+
+			Self::note_volume(pending.price)?;
+
+			// Taken before the order is destroyed so the reserve isn't refunded
+			PendingSales::<T>::remove(&_token_id);
+			let payee = Self::proceeds_recipient(_token_id, &seller);
+			let remaining = T::Currency::repatriate_reserved(&_buyer, &payee, pending.price, BalanceStatus::Free)?;
+			ensure!(remaining.is_zero(), Error::<T>::ReserveTooLow);
+			Self::refund_boost(&order, &seller);
+
+			Self::destroy_sell_order(sell_id)?;
+
+			Self::transfer_ownership(&seller, &_buyer, _token_id)?;
+			Self::record_sale(_token_id, &_buyer, &seller, pending.price);
+
+			Self::note_activity(&seller);
+			Self::deposit_event(Event::SaleConfirmed(_token_id, _buyer.clone(), pending.price));
+			Self::deposit_event(Event::NFTSold(_buyer, seller, pending.price));
+			Ok(())
+		}
+
+		/// Take back the funds of a pending purchase the seller didn't confirm in time
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,3))]
+		pub fn reclaim_pending_sale(_origin: OriginFor<T>, _token_id: TokenID) -> DispatchResult {
+			let buyer = ensure_signed(_origin)?;
+
+			let pending = Self::get_pending_sale(&_token_id).ok_or(Error::<T>::PendingSaleNotFound)?;
+			ensure!(pending.buyer == buyer, Error::<T>::PendingSaleNotFound);
+			ensure!(<frame_system::Pallet<T>>::block_number() > pending.expires_at, Error::<T>::PendingSaleNotExpired);
+
+			PendingSales::<T>::remove(&_token_id);
+			T::Currency::unreserve(&buyer, pending.price);
+
+			Self::note_activity(&buyer);
+			Self::deposit_event(Event::PendingSaleRefunded(_token_id, buyer, pending.price));
+			Ok(())
+		}
+
+		/// Turn down a pending purchase, handing the reserved funds back to the buyer
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,3))]
+		pub fn reject_sale(_origin: OriginFor<T>, _token_id: TokenID) -> DispatchResult {
+			let seller = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+			ensure!(seller == token_owner, Error::<T>::NotTokenOwner);
+
+			let pending = Self::get_pending_sale(&_token_id).ok_or(Error::<T>::PendingSaleNotFound)?;
+
+			PendingSales::<T>::remove(&_token_id);
+			T::Currency::unreserve(&pending.buyer, pending.price);
+
+			Self::note_activity(&seller);
+			Self::deposit_event(Event::PendingSaleRefunded(_token_id, pending.buyer, pending.price));
+			Ok(())
+		}
+
+		/// Start a sealed-bid auction. Bids are committed as hashes until `_commit_period`
+		/// blocks have passed, then revealed during the following `_reveal_period` blocks.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(6,4))]
+		pub fn start_sealed_auction(
+			_origin: OriginFor<T>,
+			_token_id: TokenID,
+			_min_bid: BalanceOf<T>,
+			_commit_period: T::BlockNumber,
+			_reveal_period: T::BlockNumber,
+		) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			ensure!(who == token_owner, Error::<T>::NotTokenOwner);
+			ensure!(!IsTokenOnSale::<T>::contains_key(&_token_id), Error::<T>::TokenAlreadyOnSale);
+			ensure!(!TokenInAuction::<T>::contains_key(&_token_id), Error::<T>::TokenInAuction);
+			ensure!(!TokenInInstallmentPlan::<T>::contains_key(&_token_id), Error::<T>::TokenInInstallmentPlan);
+			ensure!(!_commit_period.is_zero() && !_reveal_period.is_zero(), Error::<T>::InvalidAuctionPeriod);
+			Self::ensure_not_halted(_token_id)?;
+
+			let auction_id: AuctionID = Self::get_next_auction_id().unwrap_or(0);
+			let next_auction_id = auction_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let commit_end = now.saturating_add(_commit_period);
+			let auction = SealedAuction {
+				token_id: _token_id,
+				seller: who.clone(),
+				min_bid: _min_bid,
+				commit_end,
+				reveal_end: commit_end.saturating_add(_reveal_period),
+				bid_count: 0,
+				best_bid: None,
+			};
+
+			<NextAuctionId<T>>::put(next_auction_id);
+			SealedAuctions::<T>::insert(&auction_id, &auction);
+			TokenInAuction::<T>::insert(&_token_id, &auction_id);
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::SealedAuctionStarted(auction_id, _token_id, who));
+			Ok(())
+		}
+
+		/// Commit to a sealed bid. `_hash` is the hash of (bidder, amount, salt) and
+		/// `_deposit` is reserved as the upper bound of the bid.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,4))]
+		pub fn commit_bid(
+			_origin: OriginFor<T>,
+			_auction_id: AuctionID,
+			_hash: T::Hash,
+			_deposit: BalanceOf<T>,
+		) -> DispatchResult {
+			let bidder = ensure_signed(_origin)?;
+
+			let mut auction = Self::get_sealed_auction(&_auction_id).ok_or(Error::<T>::InvalidAuctionID)?;
+
+			ensure!(<frame_system::Pallet<T>>::block_number() <= auction.commit_end, Error::<T>::CommitPhaseOver);
+			ensure!(bidder != auction.seller, Error::<T>::SellerCannotBid);
+			Self::ensure_not_halted(auction.token_id)?;
+			ensure!(!BidCommitments::<T>::contains_key(&_auction_id, &bidder), Error::<T>::BidAlreadyCommitted);
+			ensure!(auction.bid_count < T::MaxSealedBids::get(), Error::<T>::TooManyBids);
+
+			T::Currency::reserve(&bidder, _deposit).map_err(|_| Error::<T>::NotEnoughBalance)?;
+
+			auction.bid_count += 1;
+			SealedAuctions::<T>::insert(&_auction_id, &auction);
+			BidCommitments::<T>::insert(&_auction_id, &bidder, BidCommitment { hash: _hash, deposit: _deposit });
+
+			Self::note_activity(&bidder);
+			Self::deposit_event(Event::BidCommitted(_auction_id, bidder, _deposit));
+			Ok(())
+		}
+
+		/// Reveal a committed bid. The first highest revealed bid wins,
+		/// bids that are never revealed lose but get their deposit back.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2,2))]
+		pub fn reveal_bid(
+			_origin: OriginFor<T>,
+			_auction_id: AuctionID,
+			_amount: BalanceOf<T>,
+			_salt: [u8; 32],
+		) -> DispatchResult {
+			let bidder = ensure_signed(_origin)?;
+
+			let mut auction = Self::get_sealed_auction(&_auction_id).ok_or(Error::<T>::InvalidAuctionID)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(now > auction.commit_end && now <= auction.reveal_end, Error::<T>::NotInRevealPhase);
+
+			let commitment = Self::get_bid_commitment(&_auction_id, &bidder).ok_or(Error::<T>::BidCommitmentNotFound)?;
+			ensure!(T::Hashing::hash_of(&(&bidder, _amount, _salt)) == commitment.hash, Error::<T>::InvalidBidReveal);
+			ensure!(_amount >= auction.min_bid && _amount <= commitment.deposit, Error::<T>::InvalidBidAmount);
+
+			let is_best = match &auction.best_bid {
+				Some((_, best_amount)) => _amount > *best_amount,
+				None => true,
+			};
+			if is_best {
+				auction.best_bid = Some((bidder.clone(), _amount));
+				SealedAuctions::<T>::insert(&_auction_id, &auction);
+			}
+
+			Self::note_activity(&bidder);
+			Self::deposit_event(Event::BidRevealed(_auction_id, bidder, _amount));
+			Ok(())
+		}
+
+		/// Settle a sealed auction after its reveal phase. The winner pays the seller and
+		/// receives the token, every other deposit is released.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			6 + 2 * T::MaxSealedBids::get() as u64,
+			6 + 2 * T::MaxSealedBids::get() as u64
+		) + transfer_ownership_weight::<T>())]
+		#[transactional]
+		pub fn settle_sealed_auction(_origin: OriginFor<T>, _auction_id: AuctionID) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let auction = Self::get_sealed_auction(&_auction_id).ok_or(Error::<T>::InvalidAuctionID)?;
+			ensure!(<frame_system::Pallet<T>>::block_number() > auction.reveal_end, Error::<T>::AuctionNotFinished);
+			// Settling moves the token, so it waits for the halt to pass
+			Self::ensure_not_halted(auction.token_id)?;
+
+			// Release every deposit, the winner's one is paid out to the seller first
+			let payee = Self::proceeds_recipient(auction.token_id, &auction.seller);
+			for (bidder, commitment) in BidCommitments::<T>::drain_prefix(&_auction_id) {
+				let mut deposit = commitment.deposit;
+				if let Some((winner, price)) = &auction.best_bid {
+					if bidder == *winner {
+						// A deposit slashed since the bid must not sell the token for less
+						let remaining = T::Currency::repatriate_reserved(&bidder, &payee, *price, BalanceStatus::Free)?;
+						ensure!(remaining.is_zero(), Error::<T>::ReserveTooLow);
+						deposit = deposit.saturating_sub(*price);
+					}
+				}
+				T::Currency::unreserve(&bidder, deposit);
+			}
+
+			SealedAuctions::<T>::remove(&_auction_id);
+			TokenInAuction::<T>::remove(&auction.token_id);
+
+			match auction.best_bid {
+				Some((winner, price)) => {
+					Self::note_volume(price)?;
+					Self::transfer_ownership(&auction.seller, &winner, auction.token_id)?;
+					Self::record_sale(auction.token_id, &winner, &auction.seller, price);
+					Self::deposit_event(Event::SealedAuctionSettled(_auction_id, Some(winner), price));
+				},
+				None => Self::deposit_event(Event::SealedAuctionSettled(_auction_id, None, Zero::zero())),
+			}
+
+			Self::note_activity(&who);
+			Ok(())
+		}
+
+		/// Sell a token to `_buyer` for `_total`, paid in `_installments` equal parts every
+		/// `_interval` blocks, the first one due `_interval` blocks from now. The token stays
+		/// with the seller until the last installment is paid.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(6,4))]
+		pub fn start_installment_plan(
+			_origin: OriginFor<T>,
+			_token_id: TokenID,
+			_buyer: T::AccountId,
+			_total: BalanceOf<T>,
+			_installments: u32,
+			_interval: T::BlockNumber,
+		) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			ensure!(who == token_owner, Error::<T>::NotTokenOwner);
+			ensure!(!IsTokenOnSale::<T>::contains_key(&_token_id), Error::<T>::TokenAlreadyOnSale);
+			ensure!(!TokenInAuction::<T>::contains_key(&_token_id), Error::<T>::TokenInAuction);
+			ensure!(!TokenInInstallmentPlan::<T>::contains_key(&_token_id), Error::<T>::TokenInInstallmentPlan);
+			Self::ensure_not_halted(_token_id)?;
+			ensure!(
+				_buyer != who && _installments > 0 && !_interval.is_zero() && _total >= <BalanceOf<T>>::from(_installments),
+				Error::<T>::InvalidInstallmentPlan
+			);
+
+			let plan_id: PlanID = Self::get_next_plan_id().unwrap_or(0);
+			let next_plan_id = plan_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
+
+			let plan = InstallmentPlan {
+				token_id: _token_id,
+				seller: who.clone(),
+				buyer: _buyer.clone(),
+				total: _total,
+				installments: _installments,
+				interval: _interval,
+				paid_installments: 0,
+				paid: Zero::zero(),
+				next_due: <frame_system::Pallet<T>>::block_number().saturating_add(_interval),
+			};
+
+			<NextPlanId<T>>::put(next_plan_id);
+			InstallmentPlans::<T>::insert(&plan_id, &plan);
+			TokenInInstallmentPlan::<T>::insert(&_token_id, &plan_id);
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::InstallmentPlanStarted(plan_id, _token_id, who, _buyer, _total));
+			Ok(())
+		}
+
+		/// Pay the next installment. Payments are reserved until the plan completes,
+		/// the last one transfers the token and releases the funds to the seller.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(7,8) + transfer_ownership_weight::<T>())]
+		#[transactional]
+		pub fn pay_installment(_origin: OriginFor<T>, _plan_id: PlanID) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let mut plan = Self::get_installment_plan(&_plan_id).ok_or(Error::<T>::InstallmentPlanNotFound)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+
+			ensure!(who == plan.buyer, Error::<T>::NotInstallmentBuyer);
+			ensure!(now <= Self::installment_deadline(&plan), Error::<T>::InstallmentOverdue);
+			plan.next_due = plan.next_due.saturating_add(plan.interval);
+
+			// The last installment pays whatever the equal parts left over
+			let amount = if plan.paid_installments + 1 == plan.installments {
+				plan.total.saturating_sub(plan.paid)
+			} else {
+				plan.total / <BalanceOf<T>>::from(plan.installments)
+			};
+
+			T::Currency::reserve(&who, amount).map_err(|_| Error::<T>::NotEnoughBalance)?;
+			plan.paid = plan.paid.saturating_add(amount);
+			plan.paid_installments += 1;
+
+			Self::deposit_event(Event::InstallmentPaid(_plan_id, who.clone(), amount));
+
+			if plan.paid_installments < plan.installments {
+				InstallmentPlans::<T>::insert(&_plan_id, &plan);
+				Self::note_activity(&who);
+				return Ok(());
+			}
+
+			// The last installment moves the token, so it waits for a halt to pass
+			Self::ensure_not_halted(plan.token_id)?;
+
+			Self::note_volume(plan.paid)?;
+
+			let payee = Self::proceeds_recipient(plan.token_id, &plan.seller);
+			let remaining = T::Currency::repatriate_reserved(&who, &payee, plan.paid, BalanceStatus::Free)?;
+			ensure!(remaining.is_zero(), Error::<T>::ReserveTooLow);
+
+			InstallmentPlans::<T>::remove(&_plan_id);
+			TokenInInstallmentPlan::<T>::remove(&plan.token_id);
+			Self::transfer_ownership(&plan.seller, &who, plan.token_id)?;
+			Self::record_sale(plan.token_id, &who, &plan.seller, plan.paid);
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::InstallmentPlanCompleted(_plan_id, who, plan.seller));
+			Ok(())
+		}
+
+		/// Close a plan whose buyer missed an installment past the grace period. The token
+		/// stays with the seller and the payments are forfeited or refunded per the runtime.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5,5))]
+		#[transactional]
+		pub fn claim_installment_default(_origin: OriginFor<T>, _plan_id: PlanID) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let plan = Self::get_installment_plan(&_plan_id).ok_or(Error::<T>::InstallmentPlanNotFound)?;
+			let buyer = plan.buyer;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(now > Self::installment_deadline(&plan), Error::<T>::InstallmentNotOverdue);
+
+			let forfeited = T::ForfeitInstallmentsOnDefault::get();
+			if forfeited {
+				let payee = Self::proceeds_recipient(plan.token_id, &plan.seller);
+				let remaining = T::Currency::repatriate_reserved(&buyer, &payee, plan.paid, BalanceStatus::Free)?;
+				ensure!(remaining.is_zero(), Error::<T>::ReserveTooLow);
+			} else {
+				T::Currency::unreserve(&buyer, plan.paid);
+			}
+
+			InstallmentPlans::<T>::remove(&_plan_id);
+			TokenInInstallmentPlan::<T>::remove(&plan.token_id);
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::InstallmentPlanDefaulted(_plan_id, buyer, plan.paid, forfeited));
+			Ok(())
+		}
+
+		/// Withdraw an installment plan the buyer hasn't started paying yet
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,3))]
+		pub fn cancel_installment_plan(_origin: OriginFor<T>, _plan_id: PlanID) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let plan = Self::get_installment_plan(&_plan_id).ok_or(Error::<T>::InstallmentPlanNotFound)?;
+			ensure!(who == plan.seller, Error::<T>::NotTokenOwner);
+			ensure!(plan.paid_installments == 0, Error::<T>::InstallmentPlanAlreadyStarted);
+
+			InstallmentPlans::<T>::remove(&_plan_id);
+			TokenInInstallmentPlan::<T>::remove(&plan.token_id);
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::InstallmentPlanCancelled(_plan_id));
+			Ok(())
+		}
+
+		/// Burn a token to claim the physical item it represents. `_shipping_info_hash`
+		/// is a hash of the off-chain shipping details for the fulfillment backend.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1) + burn_token_weight::<T>())]
+		pub fn redeem_physical(_origin: OriginFor<T>, _token_id: TokenID, _shipping_info_hash: T::Hash) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			ensure!(who == token_owner, Error::<T>::NotTokenOwner);
+
+			Self::burn_token(&who, _token_id)?;
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::PhysicalRedeemed(_token_id, who, _shipping_info_hash));
+			Ok(())
+		}
+
+		/// Give up a token without a recipient. The token goes to the null account,
+		/// or is burnt if the runtime says so.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(4,1)
+				+ burn_token_weight::<T>().max(transfer_ownership_weight::<T>())
+		)]
+		pub fn relinquish(_origin: OriginFor<T>, _token_id: TokenID) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			ensure!(who == token_owner, Error::<T>::NotTokenOwner);
+
+			if T::BurnRelinquished::get() {
+				Self::burn_token(&who, _token_id)?;
+			} else {
+				ensure!(!IsTokenOnSale::<T>::contains_key(&_token_id), Error::<T>::TokenAlreadyOnSale);
+				ensure!(!TokenInAuction::<T>::contains_key(&_token_id), Error::<T>::TokenInAuction);
+				ensure!(!TokenInInstallmentPlan::<T>::contains_key(&_token_id), Error::<T>::TokenInInstallmentPlan);
+
+				Self::transfer_ownership(&who, &T::NullAccount::get(), _token_id)?;
+			}
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::TokenRelinquished(_token_id, who));
+			Ok(())
+		}
+
+		/// Send the proceeds of the token's next sale to another account, such as a payment
+		/// splitter. `None` pays the owner again.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,2))]
+		pub fn set_proceeds_recipient(
+			_origin: OriginFor<T>,
+			_token_id: TokenID,
+			_recipient: Option<T::AccountId>,
+		) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let (token_owner, _) = match Self::get_nft_details(&_token_id) {
+				Some(x) => x,
+				None => Err(<Error<T>>::InvalidTokenID)?
+			};
+
+			// Listing managers can't redirect the owner's money
+			ensure!(who == token_owner, Error::<T>::NotTokenOwner);
+
+			match &_recipient {
+				Some(recipient) => ProceedsRecipient::<T>::insert(&_token_id, recipient),
+				None => ProceedsRecipient::<T>::remove(&_token_id),
+			}
+
+			Self::note_activity(&who);
+			Self::deposit_event(Event::ProceedsRecipientSet(_token_id, _recipient));
+			Ok(())
+		}
+
+		/// Flag a listing for review. Once ReportThreshold accounts reported it, the token's
+		/// trading is halted for ReportHaltDuration blocks and the reports start over.
+		/// Reports count against the owner, so cancelling and relisting doesn't drop them.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 5 + T::ReportThreshold::get() as u64))]
+		pub fn report_listing(_origin: OriginFor<T>, _token_id: TokenID, _reason_code: u8) -> DispatchResult {
+			let who = ensure_signed(_origin)?;
+
+			let threshold = T::ReportThreshold::get();
+			ensure!(threshold > 0, Error::<T>::ReportingDisabled);
+			ensure!(IsTokenOnSale::<T>::contains_key(&_token_id), Error::<T>::TokenNotOnSale);
+			// Reports during a halt would only stack up the next one
+			Self::ensure_not_halted(_token_id)?;
+			ensure!(!Reporters::<T>::contains_key(&_token_id, &who), Error::<T>::AlreadyReported);
+
+			Reporters::<T>::insert(&_token_id, &who, ());
+			let reports = Reports::<T>::mutate(&_token_id, |count| {
+				*count = count.saturating_add(1);
+				*count
+			});
+
+			Self::deposit_event(Event::ListingReported(_token_id, who, _reason_code));
+
+			// Halt when the threshold is reached, root reviews from there
+			if reports >= threshold {
+				let now = <frame_system::Pallet<T>>::block_number();
+				let until = now
+					.saturating_add(T::ReportHaltDuration::get())
+					.max(Self::get_halted_until(&_token_id).unwrap_or(now));
+				TokenHaltedUntil::<T>::insert(&_token_id, until);
+				Self::clear_reports(_token_id);
+				Self::deposit_event(Event::TokenTradingHalted(_token_id, until));
+			}
+
+			Self::note_activity(&who);
+			Ok(())
+		}
+
+		/// Halt all trading of a token until `_until` has passed. A block in the past lifts the halt.
+		/// Either way the token has been reviewed, so its open reports are dropped.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 2 + T::ReportThreshold::get() as u64))]
+		pub fn halt_token(_origin: OriginFor<T>, _token_id: TokenID, _until: T::BlockNumber) -> DispatchResult {
+			ensure_root(_origin)?;
+
+			ensure!(TokenIdToOwner::<T>::contains_key(&_token_id), Error::<T>::InvalidTokenID);
+			TokenHaltedUntil::<T>::insert(&_token_id, _until);
+			Self::clear_reports(_token_id);
+
+			Self::deposit_event(Event::TokenTradingHalted(_token_id, _until));
+			Ok(())
+		}
+
+		/// Mark an account as a verified creator
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn verify_creator(_origin: OriginFor<T>, _creator: T::AccountId) -> DispatchResult {
+			ensure_root(_origin)?;
+
+			VerifiedCreators::<T>::insert(&_creator, ());
+
+			Self::deposit_event(Event::CreatorVerified(_creator));
+			Ok(())
+		}
+
+		/// Remove an account's verified creator mark
+		#[pallet::weight(10_000 + T::DbWeight::get().writes(1))]
+		pub fn unverify_creator(_origin: OriginFor<T>, _creator: T::AccountId) -> DispatchResult {
+			ensure_root(_origin)?;
+
+			VerifiedCreators::<T>::remove(&_creator);
+
+			Self::deposit_event(Event::CreatorUnverified(_creator));
+			Ok(())
+		}
+
+		/// Cancel every listing of an account that made no signed marketplace call in the last
+		/// `_inactivity_threshold` blocks. Accounts with no recorded activity count as inactive
+		/// since genesis. `_nft_witness` must be at least the number of NFTs the account owns.
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads(2 + 2 * *_nft_witness as u64)
+				+ destroy_sell_order_weight::<T>().saturating_mul(*_nft_witness as u64)
+		)]
+		#[transactional]
+		pub fn delist_inactive(
+			_origin: OriginFor<T>,
+			_account: T::AccountId,
+			_inactivity_threshold: T::BlockNumber,
+			_nft_witness: u32,
+		) -> DispatchResult {
+			ensure_root(_origin)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let last_active = Self::get_last_active(&_account).unwrap_or_else(Zero::zero);
+			ensure!(now.saturating_sub(last_active) > _inactivity_threshold, Error::<T>::SellerStillActive);
+
+			let number_of_nfts = Self::get_number_of_nfts_owned(&_account).unwrap_or(0);
+			ensure!(number_of_nfts <= _nft_witness as u64, Error::<T>::WitnessTooLow);
+			let mut cancelled: u64 = 0;
+			for idx in 0..number_of_nfts {
+				let token_id = match Self::get_token_ids_of_owned_nfts(&_account, idx) {
+					Some(id) => id,
+					None => continue,
+				};
+				if let Some(index_in_sell_orders) = Self::is_onsale(&token_id) {
+					Self::destroy_sell_order(index_in_sell_orders)?;
+					Self::deposit_event(Event::CancelledOrder(token_id));
+					cancelled += 1;
+				}
+			}
+
+			Self::deposit_event(Event::InactiveSellerDelisted(_account, cancelled));
+			Ok(())
+		}
+
+		/// Drop every sealed bid, pending purchase and installment plan of an account and
+		/// unreserve their funds, e.g. after the account was compromised. A dropped best bid
+		/// leaves its auction without a winner. Plans it is buying on close as defaulted with
+		/// the payments refunded, the token stays with the seller. Scans every bid, pending
+		/// sale and plan, so it is limited to root, and `_bid_witness`, `_pending_witness` and
+		/// `_plan_witness` must be at least the number of stored bid commitments, pending
+		/// sales and installment plans.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			2 * *_bid_witness as u64 + *_pending_witness as u64 + *_plan_witness as u64,
+			3 * *_bid_witness as u64 + 2 * *_pending_witness as u64 + 3 * *_plan_witness as u64
+		))]
+		#[transactional]
+		pub fn release_account_reserves(
+			_origin: OriginFor<T>,
+			_account: T::AccountId,
+			_bid_witness: u32,
+			_pending_witness: u32,
+			_plan_witness: u32,
+		) -> DispatchResult {
+			ensure_root(_origin)?;
+
+			let mut released: u32 = 0;
+			let mut total: BalanceOf<T> = Zero::zero();
+
+			// Taking one past the witness is enough to tell it was too low
+			let scanned: Vec<_> = BidCommitments::<T>::iter().take(_bid_witness as usize + 1).collect();
+			ensure!(scanned.len() <= _bid_witness as usize, Error::<T>::WitnessTooLow);
+			let bids: Vec<(AuctionID, BalanceOf<T>)> = scanned.into_iter()
+				.filter(|(_, bidder, _)| *bidder == _account)
+				.map(|(auction_id, _, commitment)| (auction_id, commitment.deposit))
+				.collect();
+			for (auction_id, deposit) in bids {
+				BidCommitments::<T>::remove(&auction_id, &_account);
+				SealedAuctions::<T>::mutate(&auction_id, |auction| {
+					if let Some(auction) = auction {
+						auction.bid_count = auction.bid_count.saturating_sub(1);
+						if matches!(&auction.best_bid, Some((winner, _)) if *winner == _account) {
+							auction.best_bid = None;
+						}
+					}
+				});
+				T::Currency::unreserve(&_account, deposit);
+				released += 1;
+				total = total.saturating_add(deposit);
+			}
+
+			let scanned: Vec<_> = PendingSales::<T>::iter().take(_pending_witness as usize + 1).collect();
+			ensure!(scanned.len() <= _pending_witness as usize, Error::<T>::WitnessTooLow);
+			let purchases: Vec<TokenID> = scanned.into_iter()
+				.filter(|(_, pending)| pending.buyer == _account)
+				.map(|(token_id, _)| token_id)
+				.collect();
+			for token_id in purchases {
+				if let Some(pending) = PendingSales::<T>::take(&token_id) {
+					T::Currency::unreserve(&_account, pending.price);
+					released += 1;
+					total = total.saturating_add(pending.price);
+				}
+			}
+
+			let scanned: Vec<_> = InstallmentPlans::<T>::iter().take(_plan_witness as usize + 1).collect();
+			ensure!(scanned.len() <= _plan_witness as usize, Error::<T>::WitnessTooLow);
+			let plans: Vec<(PlanID, InstallmentPlan<T>)> = scanned.into_iter()
+				.filter(|(_, plan)| plan.buyer == _account)
+				.collect();
+			for (plan_id, plan) in plans {
+				InstallmentPlans::<T>::remove(&plan_id);
+				TokenInInstallmentPlan::<T>::remove(&plan.token_id);
+				T::Currency::unreserve(&_account, plan.paid);
+				released += 1;
+				total = total.saturating_add(plan.paid);
+				Self::deposit_event(Event::InstallmentPlanDefaulted(plan_id, _account.clone(), plan.paid, false));
+			}
+
+			Self::deposit_event(Event::AccountReservesReleased(_account, released, total));
+			Ok(())
+		}
+
+		/// Import tokens minted on another chain under their original ids. NextTokenId moves
+		/// past the highest imported id so later mints don't collide. At most MaxBatchSize
+		/// tokens per call. Tokens carry no metadata, creator or royalty here, so only
+		/// ownership is imported.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			1 + 3 * _entries.len() as u64,
+			1 + 4 * _entries.len() as u64
+		))]
+		#[transactional]
+		pub fn import_tokens(_origin: OriginFor<T>, _entries: Vec<(TokenID, T::AccountId)>) -> DispatchResult {
+			ensure_root(_origin)?;
+
+			ensure!(_entries.len() <= T::MaxBatchSize::get() as usize, Error::<T>::BatchTooLarge);
+
+			let mut next_token_id: TokenID = Self::get_next_token_id().unwrap_or(0);
+			let imported = _entries.len() as u32;
+			for (token_id, owner) in _entries {
+				ensure!(!TokenIdToOwner::<T>::contains_key(&token_id), Error::<T>::TokenAlreadyExists);
+
+				// u64::MAX is never handed out, same as for minted tokens
+				let following_id = token_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
+				next_token_id = next_token_id.max(following_id);
+
+				let number_of_nfts = Self::get_number_of_nfts_owned(&owner).unwrap_or(0);
+				<OwnerToNumberOfNFTs<T>>::insert(
+					&owner,
+					number_of_nfts.checked_add(1).ok_or(Error::<T>::StorageOverflow)?
+				);
+				TokenIdToOwner::<T>::insert(&token_id, (&owner, &number_of_nfts));
+				OwnerToTokenIds::<T>::insert(&owner, &number_of_nfts, &token_id);
+				Self::note_first_seen(&owner);
+			}
+
+			<NextTokenId<T>>::put(next_token_id);
+
+			Self::deposit_event(Event::TokensImported(imported));
+			Ok(())
+		}
+
+		/// Rebuild an account's owned token index from TokenIdToOwner, repairing
+		/// OwnerToTokenIds and OwnerToNumberOfNFTs if they went out of sync.
+		/// Scans every token, so it is limited to root, and `_token_witness` must be at least
+		/// the number of existing tokens and of the account's old index entries.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(
+			2 * *_token_witness as u64,
+			1 + 3 * *_token_witness as u64
+		))]
+		#[transactional]
+		pub fn reindex_owner(_origin: OriginFor<T>, _account: T::AccountId, _token_witness: u32) -> DispatchResult {
+			ensure_root(_origin)?;
+
+			let scanned: Vec<_> = TokenIdToOwner::<T>::iter().take(_token_witness as usize + 1).collect();
+			ensure!(scanned.len() <= _token_witness as usize, Error::<T>::WitnessTooLow);
+			let mut token_ids: Vec<TokenID> = scanned.into_iter()
+				.filter(|(_, (owner, _))| *owner == _account)
+				.map(|(token_id, _)| token_id)
+				.collect();
+			token_ids.sort_unstable();
+
+			let removed = OwnerToTokenIds::<T>::drain_prefix(&_account).take(_token_witness as usize + 1).count();
+			ensure!(removed <= _token_witness as usize, Error::<T>::WitnessTooLow);
+
+			for (idx, token_id) in token_ids.iter().enumerate() {
+				let idx = idx as u64;
+				TokenIdToOwner::<T>::insert(token_id, (&_account, idx));
+				OwnerToTokenIds::<T>::insert(&_account, idx, token_id);
+			}
+
+			let number_of_nfts = token_ids.len() as u64;
+			if number_of_nfts == 0 {
+				<OwnerToNumberOfNFTs<T>>::remove(&_account);
+			} else {
+				<OwnerToNumberOfNFTs<T>>::insert(&_account, number_of_nfts);
+			}
+
+			Self::deposit_event(Event::OwnerReindexed(_account, number_of_nfts));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+
+		/// Look up a sell order by its stable listing id
+		pub fn sell_order_by_listing_id(listing_id: ListingID) -> Option<Order<T>> {
+			Self::get_listing_index(listing_id).and_then(Self::get_sell_order)
+		}
+
+		pub fn is_verified_creator(account: T::AccountId) -> bool {
+			VerifiedCreators::<T>::contains_key(account)
+		}
+
+		/// Blocks since the token was listed, `None` if it isn't on sale
+		pub fn listing_age(token_id: TokenID) -> Option<T::BlockNumber> {
+			let order = Self::is_onsale(token_id).and_then(Self::get_sell_order)?;
+			Some(<frame_system::Pallet<T>>::block_number().saturating_sub(order.listed_at))
+		}
+
+		/// Tokens listed more than `threshold` blocks ago. Scans every sell order,
+		/// so this is meant for runtime API calls rather than extrinsics.
+		pub fn stale_listings(threshold: T::BlockNumber) -> Vec<TokenID> {
+			let now = <frame_system::Pallet<T>>::block_number();
+			SellOrders::<T>::iter_values()
+				.filter(|order| now.saturating_sub(order.listed_at) > threshold)
+				.map(|order| order.token_id)
+				.collect()
+		}
+
+		/// Views of the given tokens in input order. Only the first MaxBatchSize ids are looked up.
+		pub fn batch_token_query(token_ids: Vec<TokenID>) -> Vec<TokenView<T::AccountId, BalanceOf<T>>> {
+			let now = <frame_system::Pallet<T>>::block_number();
+			token_ids
+				.into_iter()
+				.take(T::MaxBatchSize::get() as usize)
+				.map(|token_id| {
+					let order = Self::is_onsale(token_id).and_then(Self::get_sell_order);
+					TokenView {
+						token_id,
+						owner: Self::get_nft_details(token_id).map(|(owner, _)| owner),
+						sell_price: order.as_ref().map(|order| order.sell_price),
+						in_auction: TokenInAuction::<T>::contains_key(token_id),
+						in_installment_plan: TokenInInstallmentPlan::<T>::contains_key(token_id),
+						boosted: order.and_then(|order| order.boosted_until).map_or(false, |until| until > now),
+					}
+				})
+				.collect()
+		}
+
+		/// Owners of the given tokens in input order. Only the first MaxBatchSize ids are looked up.
+		pub fn owners_of(token_ids: Vec<TokenID>) -> Vec<Option<T::AccountId>> {
+			token_ids
+				.into_iter()
+				.take(T::MaxBatchSize::get() as usize)
+				.map(|token_id| Self::get_nft_details(token_id).map(|(owner, _)| owner))
+				.collect()
+		}
+
+		/// Whether each of the given tokens exists, in input order. Only the first MaxBatchSize
+		/// ids are looked up.
+		pub fn exist(token_ids: Vec<TokenID>) -> Vec<bool> {
+			token_ids
+				.into_iter()
+				.take(T::MaxBatchSize::get() as usize)
+				.map(TokenIdToOwner::<T>::contains_key)
+				.collect()
+		}
+
+		/// Latest sales across the marketplace, oldest first
+		pub fn recent_sales() -> Vec<SaleRecord<T>> {
+			Self::get_recent_sales().into_inner()
+		}
+
+		/// Currently featured listings with the block their boost ends, soonest expiry first.
+		/// Scans every sell order, so this is meant for runtime API calls.
+		pub fn featured_listings() -> Vec<(TokenID, T::BlockNumber)> {
+			let now = <frame_system::Pallet<T>>::block_number();
+			let mut featured: Vec<(TokenID, T::BlockNumber)> = SellOrders::<T>::iter_values()
+				.filter_map(|order| match order.boosted_until {
+					Some(until) if until > now => Some((order.token_id, until)),
+					_ => None,
+				})
+				.collect();
+			featured.sort_by_key(|(_, until)| *until);
+			featured
+		}
+
+		/// Accounts owning the most tokens, at most `limit` and never more than MaxBatchSize.
+		/// Scans every owner, so this is meant for runtime API calls.
+		pub fn top_collectors(limit: u32) -> Vec<(T::AccountId, u64)> {
+			Self::top_by_count(OwnerToNumberOfNFTs::<T>::iter(), limit)
+		}
+
+		/// Accounts that minted the most tokens, at most `limit` and never more than MaxBatchSize.
+		/// Scans every creator, so this is meant for runtime API calls.
+		pub fn top_creators(limit: u32) -> Vec<(T::AccountId, u64)> {
+			Self::top_by_count(CreatorTokenCount::<T>::iter(), limit)
+		}
+
+		// Highest counts first, ties keep storage order
+		fn top_by_count(counts: impl Iterator<Item = (T::AccountId, u64)>, limit: u32) -> Vec<(T::AccountId, u64)> {
+			let mut ranked: Vec<(T::AccountId, u64)> = counts.filter(|(_, count)| *count > 0).collect();
+			ranked.sort_by_key(|(_, count)| Reverse(*count));
+			ranked.truncate(limit.min(T::MaxBatchSize::get()) as usize);
+			ranked
+		}
+
+		fn record_sale(token_id: TokenID, buyer: &T::AccountId, seller: &T::AccountId, price: BalanceOf<T>) {
+			let now = <frame_system::Pallet<T>>::block_number();
+			RecentSales::<T>::mutate(|sales| {
+				if !sales.is_empty() && sales.len() >= T::MaxRecentSales::get() as usize {
+					sales.remove(0);
+				}
+				// Only fails if the feed is configured to hold no sales
+				let _ = sales.try_push((token_id, buyer.clone(), seller.clone(), price, now));
+			});
+		}
+
+		fn note_first_seen(who: &T::AccountId) {
+			if !FirstSeen::<T>::contains_key(who) {
+				FirstSeen::<T>::insert(who, <frame_system::Pallet<T>>::block_number());
+			}
+		}
+
+		// Pays back the unused part of a boost when the listing sells before it runs out
+		fn refund_boost(order: &Order<T>, seller: &T::AccountId) {
+			let now = <frame_system::Pallet<T>>::block_number();
+			let boosted_until = match order.boosted_until {
+				Some(until) if until > now => until,
+				_ => return,
+			};
+
+			let blocks: u128 = boosted_until.saturating_sub(now).saturated_into();
+			let refund = T::BoostFee::get().saturating_mul(blocks.saturated_into());
+
+			// The sale goes through even if the fee account can't cover the refund
+			if !refund.is_zero() && T::Currency::transfer(&T::FeeAccount::get(), seller, refund, ExistenceRequirement::KeepAlive).is_ok() {
+				Self::deposit_event(Event::BoostRefunded(order.token_id, seller.clone(), refund));
+			}
+		}
+
+		// Where the proceeds of selling a token go
+		fn proceeds_recipient(token_id: TokenID, owner: &T::AccountId) -> T::AccountId {
+			Self::get_proceeds_recipient(token_id).unwrap_or_else(|| owner.clone())
+		}
+
+		// Adds a sale to this block's volume, failing if it would go past the cap
+		fn note_volume(amount: BalanceOf<T>) -> Result<(), Error<T>> {
+			let cap = T::MaxVolumePerBlock::get();
+			let volume = Self::get_block_volume().saturating_add(amount);
+			ensure!(cap.is_zero() || volume <= cap, Error::<T>::BlockVolumeCapReached);
+			BlockVolume::<T>::put(volume);
+			Ok(())
+		}
+
+		// Halts lift by themselves once their last block has passed
+		fn ensure_not_halted(token_id: TokenID) -> Result<(), Error<T>> {
+			if let Some(until) = Self::get_halted_until(&token_id) {
+				ensure!(<frame_system::Pallet<T>>::block_number() > until, Error::<T>::TokenTradingHalted);
+			}
+			Ok(())
+		}
+
+		// A halt over the due date blocks the completing payment, so the buyer gets a full
+		// grace period after the halt before the plan can be claimed as defaulted
+		fn installment_deadline(plan: &InstallmentPlan<T>) -> T::BlockNumber {
+			let due = match Self::get_halted_until(&plan.token_id) {
+				Some(until) if until > plan.next_due => until,
+				_ => plan.next_due,
+			};
+			due.saturating_add(T::InstallmentGracePeriod::get())
+		}
+
+		fn note_activity(who: &T::AccountId) {
+			LastActive::<T>::insert(who, <frame_system::Pallet<T>>::block_number());
+		}
+
+		// Checks `who` may manage the token's listing, then changes the order in place
+		fn mutate_listing(
+			who: &T::AccountId,
+			token_id: TokenID,
+			f: impl FnOnce(&mut Order<T>),
+		) -> Result<(), Error<T>> {
+			let (token_owner, _) = Self::get_nft_details(token_id).ok_or(Error::<T>::InvalidTokenID)?;
+			Self::ensure_listing_authority(who, &token_owner)?;
+
+			let sell_id = Self::is_onsale(token_id).ok_or(Error::<T>::TokenNotOnSale)?;
+			SellOrders::<T>::try_mutate(sell_id, |order| -> Result<(), Error<T>> {
+				f(order.as_mut().ok_or(Error::<T>::SellOrderNotFound)?);
+				Ok(())
+			})?;
+
+			Self::note_listing_activity(who, &token_owner);
+			Ok(())
+		}
+
+		// Owners manage their own listings, and so do the managers they appointed
+		fn ensure_listing_authority(who: &T::AccountId, owner: &T::AccountId) -> Result<(), Error<T>> {
+			ensure!(who == owner || ListingManagers::<T>::contains_key(owner, who), Error::<T>::NotListingManager);
+			Ok(())
+		}
+
+		// A manager acting keeps the owner active too, so managed listings aren't delisted
+		// as inactive. Only called once the listing change went through.
+		fn note_listing_activity(who: &T::AccountId, owner: &T::AccountId) {
+			Self::note_activity(who);
+			if who != owner {
+				Self::note_activity(owner);
+			}
+		}
+
+		// Pays the seller, closes the sell order and hands the token to the buyer
+		fn do_buy(buyer: T::AccountId, token_id: TokenID, sell_id: u128) -> DispatchResult {
+			Self::ensure_not_halted(token_id)?;
+
+			let (seller, _) = Self::get_nft_details(token_id).ok_or(Error::<T>::InvalidTokenID)?;
+			let order = Self::get_sell_order(sell_id).ok_or(Error::<T>::SellOrderNotFound)?;
+			let sell_price = order.sell_price;
+
+			ensure!(
+				!order.restrict_buyers || ListingBuyerAllowlist::<T>::contains_key(token_id, &buyer),
+				Error::<T>::BuyerNotAllowed
+			);
+
+			if order.require_confirmation {
+				return Self::request_confirmation(buyer, token_id, sell_price);
+			}
+
+			Self::note_volume(sell_price)?;
+
+			// Transfer balance
+			ensure!(T::Currency::free_balance(&buyer) >= sell_price, <Error<T>>::NotEnoughBalance);
+			let payee = Self::proceeds_recipient(token_id, &seller);
+			T::Currency::transfer(&buyer, &payee, sell_price, ExistenceRequirement::KeepAlive)?;
+			Self::refund_boost(&order, &seller);
+
+			// Delete sell order
+			Self::destroy_sell_order(sell_id)?;
+
+			Self::transfer_ownership(&seller, &buyer, token_id)?;
+			Self::record_sale(token_id, &buyer, &seller, sell_price);
+
+			Self::deposit_event(Event::NFTSold(buyer, seller, sell_price));
+			Ok(())
+		}
+
+		// Reserves the price from the buyer until the seller confirms or the timeout passes
+		fn request_confirmation(buyer: T::AccountId, token_id: TokenID, price: BalanceOf<T>) -> DispatchResult {
+			let now = <frame_system::Pallet<T>>::block_number();
+
+			// An unconfirmed purchase past its timeout gives way to the new buyer
+			if let Some(pending) = Self::get_pending_sale(&token_id) {
+				ensure!(now > pending.expires_at, Error::<T>::SaleAlreadyPending);
+				T::Currency::unreserve(&pending.buyer, pending.price);
+				Self::deposit_event(Event::PendingSaleRefunded(token_id, pending.buyer, pending.price));
+			}
+
+			T::Currency::reserve(&buyer, price).map_err(|_| Error::<T>::NotEnoughBalance)?;
+
+			let expires_at = now.saturating_add(T::SaleConfirmationTimeout::get());
+			PendingSales::<T>::insert(&token_id, PendingSale { buyer: buyer.clone(), price, expires_at });
+
+			Self::deposit_event(Event::SaleAwaitingConfirmation(token_id, buyer, price, expires_at));
+			Ok(())
+		}
+
+		// Bounded by ReportThreshold, reports are cleared whenever it is reached
+		fn clear_reports(token_id: TokenID) {
+			let _ = Reporters::<T>::remove_prefix(token_id, Some(T::ReportThreshold::get()));
+			Reports::<T>::remove(token_id);
+		}
+
+		// Bounded by MaxListingBuyers, which add_listing_buyer enforces
+		fn clear_listing_buyers(token_id: TokenID) {
+			let _ = ListingBuyerAllowlist::<T>::remove_prefix(token_id, Some(T::MaxListingBuyers::get()));
+			ListingBuyerCount::<T>::remove(token_id);
+		}
+
+		fn buy_commitment_lapsed(commitment: &BuyCommitment<T>, now: T::BlockNumber) -> bool {
+			now.saturating_sub(commitment.committed_at) > T::BuyCommitmentLifetime::get()
+		}
+
+		// Drops the listing's lapsed buy commitments and returns how many are left. Only
+		// writes if something lapsed, so a full listing fails commit_buy without writes.
+		fn prune_lapsed_buy_commitments(token_id: TokenID, now: T::BlockNumber) -> u32 {
+			let count = Self::get_buy_commitment_count(token_id);
+			let lapsed: Vec<T::AccountId> = BuyCommitments::<T>::iter_prefix(token_id)
+				.take(T::MaxBuyCommitments::get() as usize)
+				.filter(|(_, commitment)| Self::buy_commitment_lapsed(commitment, now))
+				.map(|(buyer, _)| buyer)
+				.collect();
+			if lapsed.is_empty() {
+				return count;
+			}
+
+			for buyer in &lapsed {
+				BuyCommitments::<T>::remove(token_id, buyer);
+			}
+			let count = count.saturating_sub(lapsed.len() as u32);
+			BuyCommitmentCount::<T>::insert(token_id, count);
+			count
+		}
+
+		// Bounded by MaxBuyCommitments, which commit_buy enforces
+		fn clear_buy_commitments(token_id: TokenID) {
+			let _ = BuyCommitments::<T>::remove_prefix(token_id, Some(T::MaxBuyCommitments::get()));
+			BuyCommitmentCount::<T>::remove(token_id);
+		}
+
+		// Moves a token to a new owner, keeping both owners' token indexes contiguous
+		fn transfer_ownership(from: &T::AccountId, to: &T::AccountId, token_id: TokenID) -> Result<(), Error<T>> {
+			// Every caller has closed the token's listing, auction or plan by now,
+			// or refused a token held in one
+			Self::remove_from_owner(from, token_id)?;
+			ProceedsRecipient::<T>::remove(token_id);
+			PreviousListing::<T>::remove(token_id);
+			Self::clear_listing_buyers(token_id);
+			// Reports outlive a relisting but not the owner they were made against
+			Self::clear_reports(token_id);
+
+			// Make to the owner of the NFT
+			let to_nft_count = Self::get_number_of_nfts_owned(to).unwrap_or(0);
+
+			TokenIdToOwner::<T>::insert(token_id, (to, to_nft_count));
+
+			<OwnerToNumberOfNFTs<T>>::insert(
+				to,
+				to_nft_count.checked_add(1).ok_or(Error::<T>::StorageOverflow)?
+			);
+
+			OwnerToTokenIds::<T>::insert(to, to_nft_count, token_id);
+			Self::note_first_seen(to);
+
+			Ok(())
+		}
+
+		// Removes a token from its owner's token index. The caller decides
+		// what TokenIdToOwner says about the token afterwards. A desynced index
+		// fails before anything is written, reindex_owner repairs it.
+		fn remove_from_owner(owner: &T::AccountId, token_id: TokenID) -> Result<(), Error<T>> {
+			let (_, idx) = Self::get_nft_details(token_id).ok_or(Error::<T>::InvalidTokenID)?;
+
+			let owner_nft_count = Self::get_number_of_nfts_owned(owner).ok_or(Error::<T>::NotTokenOwner)?;
+			let last_idx = owner_nft_count.checked_sub(1).ok_or(Error::<T>::StorageOverflow)?;
+
+			if idx != last_idx {
+				let last_nft_id = Self::get_token_ids_of_owned_nfts(owner, last_idx).ok_or(Error::<T>::InvalidTokenID)?;
+				OwnerToTokenIds::<T>::insert(owner, idx, last_nft_id);
+				TokenIdToOwner::<T>::insert(last_nft_id, (owner, idx));
+			}
+			OwnerToTokenIds::<T>::remove(owner, last_idx);
+
+			if last_idx == 0 {
+				<OwnerToNumberOfNFTs<T>>::remove(owner);
+			} else {
+				<OwnerToNumberOfNFTs<T>>::insert(owner, last_idx);
+			}
+
+			Ok(())
+		}
+
+		// Destroys a token that is not listed, auctioned or sold in installments
+		fn burn_token(owner: &T::AccountId, token_id: TokenID) -> Result<(), Error<T>> {
+			ensure!(!IsTokenOnSale::<T>::contains_key(&token_id), Error::<T>::TokenAlreadyOnSale);
+			ensure!(!TokenInAuction::<T>::contains_key(&token_id), Error::<T>::TokenInAuction);
+			ensure!(!TokenInInstallmentPlan::<T>::contains_key(&token_id), Error::<T>::TokenInInstallmentPlan);
+
+			Self::remove_from_owner(owner, token_id)?;
+			TokenIdToOwner::<T>::remove(token_id);
+			TokenSeed::<T>::remove(token_id);
+			ProceedsRecipient::<T>::remove(token_id);
+			PreviousListing::<T>::remove(token_id);
+			TokenHaltedUntil::<T>::remove(token_id);
+			// Normally gone with the listing already, but an imported token may reuse this id
+			Self::clear_listing_buyers(token_id);
+			Self::clear_buy_commitments(token_id);
+			Self::clear_reports(token_id);
+
+			Ok(())
+		}
+
+		fn destroy_sell_order(index_in_sell_orders: u128) -> Result<(), Error<T>> {
+
+			let order = Self::get_sell_order(index_in_sell_orders).ok_or(Error::<T>::SellOrderNotFound)?;
+			let token_id: TokenID = order.token_id;
+
+			// Get the index of the last order in SellOrders
+			let last_index_in_sell_orders = Self::get_number_of_sell_orders().unwrap() - 1;
+
+			if index_in_sell_orders != last_index_in_sell_orders {
+
+				// Order at the last index
+				let order_at_last_index = match Self::get_sell_order(&last_index_in_sell_orders) {
+					Some(order) => order,
+					None => Err(<Error<T>>::SellOrderNotFound)?
+				};
+
+				let token_id_of_last_order = order_at_last_index.token_id;
+				
+				// Insert last order at index of deleted order
+				SellOrders::<T>::insert(&index_in_sell_orders, &order_at_last_index);
+				IsTokenOnSale::<T>::insert(&token_id_of_last_order, &index_in_sell_orders);
+				ListingIdToIndex::<T>::insert(&order_at_last_index.listing_id, &index_in_sell_orders);
+			}
+
+			// A purchase still waiting for confirmation is refunded with the listing
+			if let Some(pending) = PendingSales::<T>::take(&token_id) {
+				T::Currency::unreserve(&pending.buyer, pending.price);
+				Self::deposit_event(Event::PendingSaleRefunded(token_id, pending.buyer, pending.price));
+			}
+
+			// Commitments only count for the listing they were made on, so a relisted
+			// or transferred token starts clean
+			Self::clear_buy_commitments(token_id);
+
 			// Remove the token id from isTokenOnSale
 			IsTokenOnSale::<T>::remove(&token_id);
+			ListingIdToIndex::<T>::remove(&order.listing_id);
 			SellOrders::<T>::remove(&last_index_in_sell_orders);
 			NumberOfSellOrders::<T>::put(&last_index_in_sell_orders);
 
@@ -0,0 +1,144 @@
+use crate as pallet_marketplace;
+use frame_support::{
+	parameter_types,
+	traits::{ConstU16, ConstU32, ConstU64, OnInitialize, Randomness},
+};
+use frame_system as system;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, Hash, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+pub const CHARLIE: u64 = 3;
+pub const DAVE: u64 = 4;
+pub const INITIAL_BALANCE: u64 = 10_000;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Marketplace: pallet_marketplace::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type Balance = u64;
+	type DustRemoval = ();
+	type Event = Event;
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+}
+
+// Deterministic per subject, so seeds can be checked in tests
+pub struct TestRandomness;
+impl Randomness<H256, u64> for TestRandomness {
+	fn random(subject: &[u8]) -> (H256, u64) {
+		(BlakeTwo256::hash(subject), System::block_number())
+	}
+}
+
+// The statics can be changed by a single test without affecting the others
+parameter_types! {
+	pub const FeeAccount: u64 = 100;
+	pub const NullAccount: u64 = 200;
+	pub static ForfeitInstallmentsOnDefault: bool = true;
+	pub static MinAccountAgeToSell: u64 = 0;
+	pub static BurnRelinquished: bool = false;
+	pub static MaxVolumePerBlock: u64 = 0;
+	pub static MinRelistPriceDelta: u64 = 0;
+}
+
+impl pallet_marketplace::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type MaxSealedBids = ConstU32<4>;
+	type InstallmentGracePeriod = ConstU64<2>;
+	type ForfeitInstallmentsOnDefault = ForfeitInstallmentsOnDefault;
+	type MaxBatchSize = ConstU32<5>;
+	type FeeAccount = FeeAccount;
+	type BoostFee = ConstU64<10>;
+	type MinAccountAgeToSell = MinAccountAgeToSell;
+	type NullAccount = NullAccount;
+	type BurnRelinquished = BurnRelinquished;
+	type MaxRecentSales = ConstU32<3>;
+	type SaleConfirmationTimeout = ConstU64<5>;
+	type MaxVolumePerBlock = MaxVolumePerBlock;
+	type RelistWindow = ConstU64<10>;
+	type MinRelistPriceDelta = MinRelistPriceDelta;
+	type ReportThreshold = ConstU32<3>;
+	type ReportHaltDuration = ConstU64<10>;
+	type Randomness = TestRandomness;
+	type MaxBuyCommitments = ConstU32<4>;
+	type MaxListingBuyers = ConstU32<4>;
+	type BuyCommitmentLifetime = ConstU64<5>;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![
+			(ALICE, INITIAL_BALANCE),
+			(BOB, INITIAL_BALANCE),
+			(CHARLIE, INITIAL_BALANCE),
+			(DAVE, INITIAL_BALANCE),
+			(FeeAccount::get(), INITIAL_BALANCE),
+		],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	// Events are only deposited from block 1 on
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Marketplace::on_initialize(System::block_number());
+	}
+}
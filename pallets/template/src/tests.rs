@@ -0,0 +1,1240 @@
+use crate::{mock::*, Error, TokenView};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{OnRuntimeUpgrade, ReservableCurrency, StorageVersion},
+};
+use sp_core::H256;
+use sp_runtime::{traits::{BlakeTwo256, Hash}, DispatchError};
+
+type MarketplaceEvent = crate::Event<Test>;
+
+const SALT: [u8; 32] = [7u8; 32];
+
+fn mint(owner: u64) -> u64 {
+	let token_id = Marketplace::get_next_token_id().unwrap_or(0);
+	assert_ok!(Marketplace::mint(Origin::signed(owner)));
+	token_id
+}
+
+fn mint_and_list(owner: u64, price: u64) -> u64 {
+	let token_id = mint(owner);
+	assert_ok!(Marketplace::sell(Origin::signed(owner), token_id, price));
+	token_id
+}
+
+fn owner_of(token_id: u64) -> Option<u64> {
+	Marketplace::get_nft_details(token_id).map(|(owner, _)| owner)
+}
+
+fn buy_hash(buyer: u64, token_id: u64, price: u64, salt: [u8; 32]) -> H256 {
+	BlakeTwo256::hash_of(&(buyer, token_id, price, salt))
+}
+
+fn bid_hash(bidder: u64, amount: u64, salt: [u8; 32]) -> H256 {
+	BlakeTwo256::hash_of(&(bidder, amount, salt))
+}
+
+#[test]
+fn sealed_auction_commit_reveal_and_settle() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		// Commits until block 6, reveals until block 11
+		assert_ok!(Marketplace::start_sealed_auction(Origin::signed(ALICE), token_id, 50, 5, 5));
+		assert_noop!(
+			Marketplace::commit_bid(Origin::signed(ALICE), 0, bid_hash(ALICE, 100, SALT), 100),
+			Error::<Test>::SellerCannotBid
+		);
+
+		assert_ok!(Marketplace::commit_bid(Origin::signed(BOB), 0, bid_hash(BOB, 300, SALT), 400));
+		assert_ok!(Marketplace::commit_bid(Origin::signed(CHARLIE), 0, bid_hash(CHARLIE, 200, SALT), 250));
+		// Dave never reveals
+		assert_ok!(Marketplace::commit_bid(Origin::signed(DAVE), 0, bid_hash(DAVE, 500, SALT), 600));
+		System::assert_last_event(MarketplaceEvent::BidCommitted(0, DAVE, 600).into());
+		assert_eq!(Balances::reserved_balance(BOB), 400);
+		assert_eq!(Marketplace::get_sealed_auction(0).unwrap().bid_count, 3);
+
+		assert_noop!(Marketplace::reveal_bid(Origin::signed(BOB), 0, 300, SALT), Error::<Test>::NotInRevealPhase);
+
+		run_to_block(7);
+		assert_noop!(
+			Marketplace::commit_bid(Origin::signed(BOB), 0, bid_hash(BOB, 300, SALT), 400),
+			Error::<Test>::CommitPhaseOver
+		);
+		assert_noop!(Marketplace::reveal_bid(Origin::signed(CHARLIE), 0, 200, [0u8; 32]), Error::<Test>::InvalidBidReveal);
+		assert_ok!(Marketplace::reveal_bid(Origin::signed(BOB), 0, 300, SALT));
+		System::assert_last_event(MarketplaceEvent::BidRevealed(0, BOB, 300).into());
+		assert_ok!(Marketplace::reveal_bid(Origin::signed(CHARLIE), 0, 200, SALT));
+		assert_eq!(Marketplace::get_sealed_auction(0).unwrap().best_bid, Some((BOB, 300)));
+
+		assert_noop!(Marketplace::settle_sealed_auction(Origin::signed(DAVE), 0), Error::<Test>::AuctionNotFinished);
+
+		run_to_block(12);
+		assert_ok!(Marketplace::settle_sealed_auction(Origin::signed(DAVE), 0));
+		System::assert_last_event(MarketplaceEvent::SealedAuctionSettled(0, Some(BOB), 300).into());
+
+		assert_eq!(owner_of(token_id), Some(BOB));
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE + 300);
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE - 300);
+		for bidder in [BOB, CHARLIE, DAVE] {
+			assert_eq!(Balances::reserved_balance(bidder), 0);
+		}
+		assert_eq!(Balances::free_balance(CHARLIE), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(DAVE), INITIAL_BALANCE);
+		assert!(Marketplace::get_sealed_auction(0).is_none());
+		assert_eq!(Marketplace::is_in_auction(token_id), None);
+	});
+}
+
+#[test]
+fn sealed_auction_fails_to_settle_below_the_price() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::start_sealed_auction(Origin::signed(ALICE), token_id, 50, 5, 5));
+		assert_ok!(Marketplace::commit_bid(Origin::signed(BOB), 0, bid_hash(BOB, 300, SALT), 400));
+
+		run_to_block(7);
+		assert_ok!(Marketplace::reveal_bid(Origin::signed(BOB), 0, 300, SALT));
+		let _ = Balances::slash_reserved(&BOB, 150);
+
+		run_to_block(12);
+		assert_noop!(Marketplace::settle_sealed_auction(Origin::signed(DAVE), 0), Error::<Test>::ReserveTooLow);
+		assert_eq!(owner_of(token_id), Some(ALICE));
+	});
+}
+
+#[test]
+fn listing_manager_lists_and_cancels_but_cannot_move_tokens() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::set_listing_manager(Origin::signed(ALICE), BOB, true));
+
+		assert_ok!(Marketplace::sell(Origin::signed(BOB), token_id, 100));
+		assert_eq!(Marketplace::is_onsale(token_id), Some(0));
+		assert_ok!(Marketplace::cancel_order(Origin::signed(BOB), token_id));
+		assert_eq!(Marketplace::is_onsale(token_id), None);
+
+		assert_noop!(Marketplace::sell(Origin::signed(CHARLIE), token_id, 100), Error::<Test>::NotListingManager);
+		assert_noop!(Marketplace::relinquish(Origin::signed(BOB), token_id), Error::<Test>::NotTokenOwner);
+		assert_noop!(
+			Marketplace::start_installment_plan(Origin::signed(BOB), token_id, BOB, 100, 1, 10),
+			Error::<Test>::NotTokenOwner
+		);
+		assert_eq!(owner_of(token_id), Some(ALICE));
+
+		assert_ok!(Marketplace::set_listing_manager(Origin::signed(ALICE), BOB, false));
+		assert_noop!(Marketplace::sell(Origin::signed(BOB), token_id, 100), Error::<Test>::NotListingManager);
+	});
+}
+
+#[test]
+fn mint_stops_before_the_last_token_id() {
+	new_test_ext().execute_with(|| {
+		crate::NextTokenId::<Test>::put(u64::MAX - 1);
+
+		assert_ok!(Marketplace::mint(Origin::signed(ALICE)));
+		assert_eq!(owner_of(u64::MAX - 1), Some(ALICE));
+		assert_eq!(Marketplace::get_next_token_id(), Some(u64::MAX));
+
+		// The failing mint leaves every storage item as it was
+		assert_noop!(Marketplace::mint(Origin::signed(BOB)), Error::<Test>::StorageOverflow);
+		assert_eq!(owner_of(u64::MAX), None);
+		assert_eq!(Marketplace::get_number_of_nfts_owned(ALICE), Some(1));
+	});
+}
+
+#[test]
+fn installment_plan_completes_with_the_last_payment() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		// First installment due at block 11
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), token_id, BOB, 300, 3, 10));
+		System::assert_last_event(MarketplaceEvent::InstallmentPlanStarted(0, token_id, ALICE, BOB, 300).into());
+
+		assert_noop!(Marketplace::pay_installment(Origin::signed(CHARLIE), 0), Error::<Test>::NotInstallmentBuyer);
+
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		assert_eq!(Balances::reserved_balance(BOB), 100);
+		assert_eq!(owner_of(token_id), Some(ALICE));
+		assert_noop!(
+			Marketplace::cancel_installment_plan(Origin::signed(ALICE), 0),
+			Error::<Test>::InstallmentPlanAlreadyStarted
+		);
+		assert_noop!(Marketplace::sell(Origin::signed(ALICE), token_id, 100), Error::<Test>::TokenInInstallmentPlan);
+
+		run_to_block(21);
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		assert_eq!(Balances::reserved_balance(BOB), 200);
+
+		run_to_block(31);
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		System::assert_last_event(MarketplaceEvent::InstallmentPlanCompleted(0, BOB, ALICE).into());
+
+		assert_eq!(owner_of(token_id), Some(BOB));
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE + 300);
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE - 300);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert!(Marketplace::get_installment_plan(0).is_none());
+		assert_eq!(Marketplace::is_in_installment_plan(token_id), None);
+	});
+}
+
+#[test]
+fn installment_default_forfeits_payments_to_the_seller() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), token_id, BOB, 300, 3, 10));
+		// Paying early moves the next installment to block 21, overdue after 23
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+
+		run_to_block(23);
+		assert_noop!(
+			Marketplace::claim_installment_default(Origin::signed(ALICE), 0),
+			Error::<Test>::InstallmentNotOverdue
+		);
+
+		run_to_block(24);
+		assert_noop!(Marketplace::pay_installment(Origin::signed(BOB), 0), Error::<Test>::InstallmentOverdue);
+		assert_ok!(Marketplace::claim_installment_default(Origin::signed(CHARLIE), 0));
+		System::assert_last_event(MarketplaceEvent::InstallmentPlanDefaulted(0, BOB, 100, true).into());
+
+		assert_eq!(owner_of(token_id), Some(ALICE));
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE + 100);
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE - 100);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert!(Marketplace::get_installment_plan(0).is_none());
+		assert_eq!(Marketplace::is_in_installment_plan(token_id), None);
+	});
+}
+
+#[test]
+fn installment_default_can_refund_the_buyer() {
+	new_test_ext().execute_with(|| {
+		ForfeitInstallmentsOnDefault::set(false);
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), token_id, BOB, 300, 3, 10));
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+
+		run_to_block(24);
+		assert_ok!(Marketplace::claim_installment_default(Origin::signed(ALICE), 0));
+		System::assert_last_event(MarketplaceEvent::InstallmentPlanDefaulted(0, BOB, 100, false).into());
+
+		assert_eq!(owner_of(token_id), Some(ALICE));
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+	});
+}
+
+#[test]
+fn installment_plan_fails_to_complete_below_the_total() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), token_id, BOB, 200, 2, 10));
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		let _ = Balances::slash_reserved(&BOB, 50);
+
+		run_to_block(11);
+		assert_noop!(Marketplace::pay_installment(Origin::signed(BOB), 0), Error::<Test>::ReserveTooLow);
+		assert_eq!(owner_of(token_id), Some(ALICE));
+	});
+}
+
+#[test]
+fn listing_id_resolves_after_swap_remove() {
+	new_test_ext().execute_with(|| {
+		let first = mint_and_list(ALICE, 100);
+		let _second = mint_and_list(ALICE, 200);
+		let third = mint_and_list(ALICE, 300);
+		assert_eq!(Marketplace::get_listing_nonce(), Some(3));
+
+		// The last order moves into the cancelled order's slot
+		assert_ok!(Marketplace::cancel_order(Origin::signed(ALICE), first));
+		assert_eq!(Marketplace::is_onsale(third), Some(0));
+
+		let order = Marketplace::sell_order_by_listing_id(2).unwrap();
+		assert_eq!(order.token_id, third);
+		assert_eq!(order.sell_price, 300);
+		assert_eq!(Marketplace::get_listing_index(2), Some(0));
+		assert!(Marketplace::sell_order_by_listing_id(0).is_none());
+
+		// Relisting gets a new id
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), first, 150));
+		assert_eq!(Marketplace::get_sell_order(Marketplace::is_onsale(first).unwrap()).unwrap().listing_id, 3);
+	});
+}
+
+#[test]
+fn runtime_upgrade_migrates_old_sell_orders() {
+	new_test_ext().execute_with(|| {
+		run_to_block(5);
+		StorageVersion::new(0).put::<Marketplace>();
+
+		// Orders as stored before listing ids existed: (token_id, sell_price)
+		for (index, token_id, price) in [(0u128, 7u64, 100u64), (1, 3, 250)] {
+			frame_support::storage::unhashed::put(&crate::SellOrders::<Test>::hashed_key_for(index), &(token_id, price));
+			crate::IsTokenOnSale::<Test>::insert(token_id, index);
+		}
+		crate::NumberOfSellOrders::<Test>::put(2);
+
+		Marketplace::on_runtime_upgrade();
+
+		assert_eq!(StorageVersion::get::<Marketplace>(), StorageVersion::new(1));
+		let order = Marketplace::get_sell_order(1).unwrap();
+		assert_eq!((order.token_id, order.sell_price, order.listing_id, order.listed_at), (3, 250, 1, 5));
+		assert!(!order.require_commit && !order.require_confirmation && !order.restrict_buyers);
+		assert_eq!(order.boosted_until, None);
+		assert_eq!(Marketplace::sell_order_by_listing_id(0).unwrap().token_id, 7);
+		assert_eq!(Marketplace::get_listing_nonce(), Some(2));
+
+		// Running it again leaves the migrated orders alone
+		crate::ListingNonce::<Test>::put(9);
+		Marketplace::on_runtime_upgrade();
+		assert_eq!(Marketplace::get_listing_nonce(), Some(9));
+	});
+}
+
+#[test]
+fn listing_age_and_stale_listings() {
+	new_test_ext().execute_with(|| {
+		let old = mint_and_list(ALICE, 100);
+		run_to_block(5);
+		let recent = mint_and_list(ALICE, 100);
+		let unlisted = mint(ALICE);
+
+		run_to_block(8);
+		assert_eq!(Marketplace::listing_age(old), Some(7));
+		assert_eq!(Marketplace::listing_age(recent), Some(3));
+		assert_eq!(Marketplace::listing_age(unlisted), None);
+
+		assert_eq!(Marketplace::stale_listings(5), vec![old]);
+		let mut stale = Marketplace::stale_listings(2);
+		stale.sort_unstable();
+		assert_eq!(stale, vec![old, recent]);
+	});
+}
+
+#[test]
+fn reindex_owner_repairs_a_desynced_index() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..3 {
+			mint(ALICE);
+		}
+		let bobs = mint(BOB);
+
+		// Count too high, a hole at index 1 and a stray entry at index 4
+		crate::OwnerToNumberOfNFTs::<Test>::insert(ALICE, 5);
+		crate::OwnerToTokenIds::<Test>::remove(ALICE, 1);
+		crate::OwnerToTokenIds::<Test>::insert(ALICE, 4, 9);
+
+		assert_noop!(Marketplace::reindex_owner(Origin::root(), ALICE, 3), Error::<Test>::WitnessTooLow);
+		assert_noop!(Marketplace::reindex_owner(Origin::signed(ALICE), ALICE, 4), DispatchError::BadOrigin);
+
+		assert_ok!(Marketplace::reindex_owner(Origin::root(), ALICE, 4));
+		System::assert_last_event(MarketplaceEvent::OwnerReindexed(ALICE, 3).into());
+
+		assert_eq!(Marketplace::get_number_of_nfts_owned(ALICE), Some(3));
+		for idx in 0..3u64 {
+			let token_id = Marketplace::get_token_ids_of_owned_nfts(ALICE, idx).unwrap();
+			assert_eq!(token_id, idx);
+			assert_eq!(Marketplace::get_nft_details(token_id), Some((ALICE, idx)));
+		}
+		assert_eq!(Marketplace::get_token_ids_of_owned_nfts(ALICE, 4), None);
+		assert_eq!(Marketplace::get_nft_details(bobs), Some((BOB, 0)));
+	});
+}
+
+#[test]
+fn commit_then_reveal_buys_the_listing() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_commit(Origin::signed(ALICE), token_id, true));
+
+		assert_noop!(Marketplace::buy(Origin::signed(BOB), token_id), Error::<Test>::CommitRequired);
+		assert_noop!(
+			Marketplace::reveal_buy(Origin::signed(BOB), token_id, 100, SALT),
+			Error::<Test>::BuyCommitmentNotFound
+		);
+
+		assert_ok!(Marketplace::commit_buy(Origin::signed(BOB), token_id, buy_hash(BOB, token_id, 100, SALT)));
+		System::assert_last_event(MarketplaceEvent::BuyCommitted(token_id, BOB).into());
+		assert_noop!(Marketplace::reveal_buy(Origin::signed(BOB), token_id, 100, SALT), Error::<Test>::RevealTooEarly);
+
+		run_to_block(2);
+		assert_ok!(Marketplace::reveal_buy(Origin::signed(BOB), token_id, 100, SALT));
+
+		assert_eq!(owner_of(token_id), Some(BOB));
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE + 100);
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE - 100);
+		assert!(Marketplace::get_buy_commitment(token_id, BOB).is_none());
+		assert_eq!(Marketplace::get_buy_commitment_count(token_id), 0);
+	});
+}
+
+#[test]
+fn mismatched_or_repriced_buy_reveal_fails() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_commit(Origin::signed(ALICE), token_id, true));
+		assert_ok!(Marketplace::commit_buy(Origin::signed(BOB), token_id, buy_hash(BOB, token_id, 100, SALT)));
+		assert_ok!(Marketplace::commit_buy(Origin::signed(CHARLIE), token_id, buy_hash(CHARLIE, token_id, 100, SALT)));
+		assert_eq!(Marketplace::get_buy_commitment_count(token_id), 2);
+
+		run_to_block(2);
+		assert_noop!(
+			Marketplace::reveal_buy(Origin::signed(BOB), token_id, 100, [0u8; 32]),
+			Error::<Test>::InvalidBuyReveal
+		);
+
+		// A reprice after the commitment can't make the committed buyer pay more
+		assert_ok!(Marketplace::batch_update_prices(Origin::signed(ALICE), vec![(token_id, 150)]));
+		assert_noop!(
+			Marketplace::reveal_buy(Origin::signed(BOB), token_id, 100, SALT),
+			Error::<Test>::ListingPriceChanged
+		);
+		assert_noop!(
+			Marketplace::reveal_buy(Origin::signed(BOB), token_id, 150, SALT),
+			Error::<Test>::InvalidBuyReveal
+		);
+
+		// Commitments end with the listing
+		assert_ok!(Marketplace::cancel_order(Origin::signed(ALICE), token_id));
+		assert!(Marketplace::get_buy_commitment(token_id, BOB).is_none());
+		assert!(Marketplace::get_buy_commitment(token_id, CHARLIE).is_none());
+		assert_eq!(Marketplace::get_buy_commitment_count(token_id), 0);
+		assert_eq!(owner_of(token_id), Some(ALICE));
+	});
+}
+
+#[test]
+fn buy_commitments_are_capped_per_listing() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_commit(Origin::signed(ALICE), token_id, true));
+
+		for buyer in 10..14u64 {
+			assert_ok!(Marketplace::commit_buy(Origin::signed(buyer), token_id, buy_hash(buyer, token_id, 100, SALT)));
+		}
+		assert_noop!(
+			Marketplace::commit_buy(Origin::signed(14), token_id, buy_hash(14, token_id, 100, SALT)),
+			Error::<Test>::TooManyBuyCommitments
+		);
+		// Replacing an existing commitment doesn't take another slot
+		assert_ok!(Marketplace::commit_buy(Origin::signed(10), token_id, buy_hash(10, token_id, 100, [1u8; 32])));
+		assert_eq!(Marketplace::get_buy_commitment_count(token_id), 4);
+	});
+}
+
+#[test]
+fn lapsed_buy_commitments_free_their_slots() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_commit(Origin::signed(ALICE), token_id, true));
+		for buyer in [BOB, 10, 11, 12] {
+			assert_ok!(Marketplace::commit_buy(Origin::signed(buyer), token_id, buy_hash(buyer, token_id, 100, SALT)));
+		}
+
+		// Committed in block 1, revealable up to block 6
+		run_to_block(7);
+		assert_noop!(
+			Marketplace::reveal_buy(Origin::signed(BOB), token_id, 100, SALT),
+			Error::<Test>::BuyCommitmentExpired
+		);
+
+		assert_ok!(Marketplace::commit_buy(Origin::signed(CHARLIE), token_id, buy_hash(CHARLIE, token_id, 100, SALT)));
+		assert_eq!(Marketplace::get_buy_commitment_count(token_id), 1);
+		assert!(Marketplace::get_buy_commitment(token_id, BOB).is_none());
+
+		run_to_block(8);
+		assert_ok!(Marketplace::reveal_buy(Origin::signed(CHARLIE), token_id, 100, SALT));
+		assert_eq!(owner_of(token_id), Some(CHARLIE));
+	});
+}
+
+#[test]
+fn batch_update_prices_reprices_three_listings() {
+	new_test_ext().execute_with(|| {
+		let tokens: Vec<u64> = (0..3).map(|_| mint_and_list(ALICE, 100)).collect();
+		let unlisted = mint(ALICE);
+
+		assert_noop!(
+			Marketplace::batch_update_prices(Origin::signed(ALICE), vec![(tokens[0], 500), (unlisted, 500)]),
+			Error::<Test>::TokenNotOnSale
+		);
+		assert_noop!(
+			Marketplace::batch_update_prices(Origin::signed(BOB), vec![(tokens[0], 500)]),
+			Error::<Test>::NotListingManager
+		);
+		assert_noop!(
+			Marketplace::batch_update_prices(Origin::signed(ALICE), vec![(tokens[0], 500); 6]),
+			Error::<Test>::BatchTooLarge
+		);
+
+		let updates = vec![(tokens[0], 110), (tokens[1], 120), (tokens[2], 130)];
+		assert_ok!(Marketplace::batch_update_prices(Origin::signed(ALICE), updates.clone()));
+
+		for (token_id, price) in updates {
+			let order = Marketplace::get_sell_order(Marketplace::is_onsale(token_id).unwrap()).unwrap();
+			assert_eq!(order.sell_price, price);
+			System::assert_has_event(MarketplaceEvent::PriceUpdated(token_id, price).into());
+		}
+	});
+}
+
+#[test]
+fn root_verifies_creators() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Marketplace::verify_creator(Origin::signed(ALICE), ALICE), DispatchError::BadOrigin);
+
+		assert_ok!(Marketplace::verify_creator(Origin::root(), ALICE));
+		System::assert_last_event(MarketplaceEvent::CreatorVerified(ALICE).into());
+		assert!(Marketplace::is_verified_creator(ALICE));
+		assert!(!Marketplace::is_verified_creator(BOB));
+
+		assert_ok!(Marketplace::unverify_creator(Origin::root(), ALICE));
+		System::assert_last_event(MarketplaceEvent::CreatorUnverified(ALICE).into());
+		assert!(!Marketplace::is_verified_creator(ALICE));
+	});
+}
+
+#[test]
+fn boosted_listing_is_featured_until_it_expires() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		let plain = mint_and_list(ALICE, 100);
+
+		assert_noop!(
+			Marketplace::boost_listing(Origin::signed(ALICE), token_id, 0),
+			Error::<Test>::InvalidBoostDuration
+		);
+
+		assert_ok!(Marketplace::boost_listing(Origin::signed(ALICE), token_id, 5));
+		System::assert_last_event(MarketplaceEvent::ListingBoosted(token_id, 6, 50).into());
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE - 50);
+		assert_eq!(Balances::free_balance(FeeAccount::get()), INITIAL_BALANCE + 50);
+		assert_eq!(Marketplace::featured_listings(), vec![(token_id, 6)]);
+
+		// Soonest expiry first
+		assert_ok!(Marketplace::boost_listing(Origin::signed(ALICE), plain, 2));
+		assert_eq!(Marketplace::featured_listings(), vec![(plain, 3), (token_id, 6)]);
+
+		run_to_block(6);
+		assert!(Marketplace::featured_listings().is_empty());
+	});
+}
+
+#[test]
+fn desynced_owner_index_fails_instead_of_panicking() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		// Claims a second token that the index doesn't have
+		crate::OwnerToNumberOfNFTs::<Test>::insert(ALICE, 2);
+
+		assert_noop!(
+			Marketplace::redeem_physical(Origin::signed(ALICE), token_id, H256::zero()),
+			Error::<Test>::InvalidTokenID
+		);
+
+		assert_ok!(Marketplace::reindex_owner(Origin::root(), ALICE, 1));
+		assert_ok!(Marketplace::redeem_physical(Origin::signed(ALICE), token_id, H256::zero()));
+		assert_eq!(Marketplace::get_number_of_nfts_owned(ALICE), None);
+	});
+}
+
+#[test]
+fn redeem_physical_burns_the_token() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		let listed = mint_and_list(ALICE, 100);
+		let shipping_info_hash = BlakeTwo256::hash(b"221B Baker Street");
+
+		assert_noop!(
+			Marketplace::redeem_physical(Origin::signed(ALICE), listed, shipping_info_hash),
+			Error::<Test>::TokenAlreadyOnSale
+		);
+		assert_noop!(
+			Marketplace::redeem_physical(Origin::signed(BOB), token_id, shipping_info_hash),
+			Error::<Test>::NotTokenOwner
+		);
+
+		assert_ok!(Marketplace::redeem_physical(Origin::signed(ALICE), token_id, shipping_info_hash));
+		System::assert_last_event(MarketplaceEvent::PhysicalRedeemed(token_id, ALICE, shipping_info_hash).into());
+
+		assert_eq!(owner_of(token_id), None);
+		assert_eq!(Marketplace::get_number_of_nfts_owned(ALICE), Some(1));
+		assert_eq!(Marketplace::get_token_ids_of_owned_nfts(ALICE, 0), Some(listed));
+		assert_eq!(Marketplace::get_nft_details(listed), Some((ALICE, 0)));
+	});
+}
+
+#[test]
+fn burning_clears_the_token_state_for_a_reimported_id() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::halt_token(Origin::root(), token_id, 50));
+		assert_ok!(Marketplace::set_proceeds_recipient(Origin::signed(ALICE), token_id, Some(DAVE)));
+
+		assert_ok!(Marketplace::redeem_physical(Origin::signed(ALICE), token_id, H256::zero()));
+		assert_eq!(Marketplace::get_halted_until(token_id), None);
+		assert_eq!(Marketplace::get_proceeds_recipient(token_id), None);
+		assert_eq!(Marketplace::get_token_seed(token_id), None);
+
+		// The imported token must not inherit the halt
+		assert_ok!(Marketplace::import_tokens(Origin::root(), vec![(token_id, BOB)]));
+		assert_ok!(Marketplace::sell(Origin::signed(BOB), token_id, 100));
+		assert_ok!(Marketplace::buy(Origin::signed(CHARLIE), token_id));
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE + 100);
+	});
+}
+
+#[test]
+fn inactive_seller_is_delisted() {
+	new_test_ext().execute_with(|| {
+		let first = mint_and_list(ALICE, 100);
+		let second = mint_and_list(ALICE, 200);
+		let bobs = mint_and_list(BOB, 300);
+
+		run_to_block(15);
+		assert_ok!(Marketplace::mint(Origin::signed(BOB)));
+
+		run_to_block(20);
+		assert_noop!(
+			Marketplace::delist_inactive(Origin::root(), BOB, 10, 2),
+			Error::<Test>::SellerStillActive
+		);
+		assert_noop!(Marketplace::delist_inactive(Origin::root(), ALICE, 10, 1), Error::<Test>::WitnessTooLow);
+
+		assert_ok!(Marketplace::delist_inactive(Origin::root(), ALICE, 10, 2));
+		System::assert_last_event(MarketplaceEvent::InactiveSellerDelisted(ALICE, 2).into());
+
+		assert_eq!(Marketplace::is_onsale(first), None);
+		assert_eq!(Marketplace::is_onsale(second), None);
+		assert_eq!(Marketplace::is_onsale(bobs), Some(0));
+		assert_eq!(Marketplace::get_number_of_sell_orders(), Some(1));
+		assert_eq!(owner_of(first), Some(ALICE));
+	});
+}
+
+#[test]
+fn only_successful_calls_count_as_activity() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::set_listing_manager(Origin::signed(ALICE), BOB, true));
+
+		run_to_block(15);
+		assert_noop!(Marketplace::sell(Origin::signed(CHARLIE), token_id, 100), Error::<Test>::NotListingManager);
+		assert_eq!(Marketplace::get_last_active(CHARLIE), None);
+
+		// A manager acting keeps the owner active too
+		assert_ok!(Marketplace::sell(Origin::signed(BOB), token_id, 100));
+		assert_eq!(Marketplace::get_last_active(BOB), Some(15));
+		assert_eq!(Marketplace::get_last_active(ALICE), Some(15));
+
+		run_to_block(20);
+		assert_noop!(
+			Marketplace::delist_inactive(Origin::root(), ALICE, 10, 1),
+			Error::<Test>::SellerStillActive
+		);
+	});
+}
+
+#[test]
+fn batch_token_query_returns_a_view_per_token() {
+	new_test_ext().execute_with(|| {
+		let listed = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::boost_listing(Origin::signed(ALICE), listed, 5));
+		let auctioned = mint(BOB);
+		assert_ok!(Marketplace::start_sealed_auction(Origin::signed(BOB), auctioned, 10, 5, 5));
+		let plain = mint(CHARLIE);
+
+		let views = Marketplace::batch_token_query(vec![listed, auctioned, plain, 99]);
+		assert_eq!(
+			views,
+			vec![
+				TokenView {
+					token_id: listed,
+					owner: Some(ALICE),
+					sell_price: Some(100),
+					in_auction: false,
+					in_installment_plan: false,
+					boosted: true,
+				},
+				TokenView {
+					token_id: auctioned,
+					owner: Some(BOB),
+					sell_price: None,
+					in_auction: true,
+					in_installment_plan: false,
+					boosted: false,
+				},
+				TokenView {
+					token_id: plain,
+					owner: Some(CHARLIE),
+					sell_price: None,
+					in_auction: false,
+					in_installment_plan: false,
+					boosted: false,
+				},
+				TokenView {
+					token_id: 99,
+					owner: None,
+					sell_price: None,
+					in_auction: false,
+					in_installment_plan: false,
+					boosted: false,
+				},
+			]
+		);
+
+		// Only the first MaxBatchSize ids are looked up
+		assert_eq!(Marketplace::batch_token_query(vec![listed; 7]).len(), 5);
+	});
+}
+
+#[test]
+fn new_accounts_cannot_sell_until_old_enough() {
+	new_test_ext().execute_with(|| {
+		MinAccountAgeToSell::set(10);
+		let aged = mint(ALICE);
+
+		run_to_block(5);
+		let fresh = mint(BOB);
+		assert_eq!(Marketplace::get_first_seen(BOB), Some(5));
+		assert_noop!(Marketplace::sell(Origin::signed(BOB), fresh, 100), Error::<Test>::AccountTooNew);
+
+		run_to_block(11);
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), aged, 100));
+		assert_noop!(Marketplace::sell(Origin::signed(BOB), fresh, 100), Error::<Test>::AccountTooNew);
+
+		run_to_block(15);
+		assert_ok!(Marketplace::sell(Origin::signed(BOB), fresh, 100));
+	});
+}
+
+#[test]
+fn relinquished_token_goes_to_the_null_account() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		let listed = mint_and_list(ALICE, 100);
+
+		assert_noop!(Marketplace::relinquish(Origin::signed(ALICE), listed), Error::<Test>::TokenAlreadyOnSale);
+
+		assert_ok!(Marketplace::relinquish(Origin::signed(ALICE), token_id));
+		System::assert_last_event(MarketplaceEvent::TokenRelinquished(token_id, ALICE).into());
+		assert_eq!(owner_of(token_id), Some(NullAccount::get()));
+		assert_eq!(Marketplace::get_number_of_nfts_owned(ALICE), Some(1));
+		assert_eq!(Marketplace::get_number_of_nfts_owned(NullAccount::get()), Some(1));
+	});
+}
+
+#[test]
+fn relinquished_token_can_be_burnt() {
+	new_test_ext().execute_with(|| {
+		BurnRelinquished::set(true);
+		let token_id = mint(ALICE);
+
+		assert_ok!(Marketplace::relinquish(Origin::signed(ALICE), token_id));
+		assert_eq!(owner_of(token_id), None);
+		assert_eq!(Marketplace::get_number_of_nfts_owned(ALICE), None);
+		assert_eq!(Marketplace::get_number_of_nfts_owned(NullAccount::get()), None);
+	});
+}
+
+#[test]
+fn recent_sales_keep_the_latest_sales_in_order() {
+	new_test_ext().execute_with(|| {
+		for (block, price) in [(1u64, 10u64), (2, 20), (3, 30), (4, 40)] {
+			run_to_block(block);
+			let token_id = mint_and_list(ALICE, price);
+			assert_ok!(Marketplace::buy(Origin::signed(BOB), token_id));
+		}
+
+		assert_eq!(
+			Marketplace::recent_sales(),
+			vec![(1, BOB, ALICE, 20, 2), (2, BOB, ALICE, 30, 3), (3, BOB, ALICE, 40, 4)]
+		);
+	});
+}
+
+#[test]
+fn confirmed_sale_moves_the_token() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_confirmation(Origin::signed(ALICE), token_id, true));
+
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), token_id));
+		System::assert_last_event(MarketplaceEvent::SaleAwaitingConfirmation(token_id, BOB, 100, 6).into());
+		assert_eq!(Balances::reserved_balance(BOB), 100);
+		assert_eq!(owner_of(token_id), Some(ALICE));
+		assert_noop!(Marketplace::buy(Origin::signed(CHARLIE), token_id), Error::<Test>::SaleAlreadyPending);
+
+		assert_noop!(
+			Marketplace::confirm_sale(Origin::signed(ALICE), token_id, CHARLIE),
+			Error::<Test>::PendingSaleNotFound
+		);
+		assert_ok!(Marketplace::confirm_sale(Origin::signed(ALICE), token_id, BOB));
+		System::assert_has_event(MarketplaceEvent::SaleConfirmed(token_id, BOB, 100).into());
+
+		assert_eq!(owner_of(token_id), Some(BOB));
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE + 100);
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE - 100);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert!(Marketplace::get_pending_sale(token_id).is_none());
+		assert_eq!(Marketplace::is_onsale(token_id), None);
+	});
+}
+
+#[test]
+fn unconfirmed_sale_times_out_and_is_reclaimed() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_confirmation(Origin::signed(ALICE), token_id, true));
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), token_id));
+
+		run_to_block(4);
+		assert_noop!(
+			Marketplace::reclaim_pending_sale(Origin::signed(BOB), token_id),
+			Error::<Test>::PendingSaleNotExpired
+		);
+
+		run_to_block(7);
+		assert_noop!(
+			Marketplace::confirm_sale(Origin::signed(ALICE), token_id, BOB),
+			Error::<Test>::PendingSaleExpired
+		);
+		assert_ok!(Marketplace::reclaim_pending_sale(Origin::signed(BOB), token_id));
+		System::assert_last_event(MarketplaceEvent::PendingSaleRefunded(token_id, BOB, 100).into());
+
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert!(Marketplace::get_pending_sale(token_id).is_none());
+		assert_eq!(owner_of(token_id), Some(ALICE));
+		assert!(Marketplace::is_onsale(token_id).is_some());
+	});
+}
+
+#[test]
+fn sale_cannot_be_confirmed_below_the_price() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_confirmation(Origin::signed(ALICE), token_id, true));
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), token_id));
+		let _ = Balances::slash_reserved(&BOB, 30);
+
+		assert_noop!(
+			Marketplace::confirm_sale(Origin::signed(ALICE), token_id, BOB),
+			Error::<Test>::ReserveTooLow
+		);
+		assert_eq!(owner_of(token_id), Some(ALICE));
+	});
+}
+
+#[test]
+fn owner_rejects_a_pending_sale() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_confirmation(Origin::signed(ALICE), token_id, true));
+		assert_noop!(
+			Marketplace::reject_sale(Origin::signed(ALICE), token_id),
+			Error::<Test>::PendingSaleNotFound
+		);
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), token_id));
+
+		assert_noop!(Marketplace::reject_sale(Origin::signed(BOB), token_id), Error::<Test>::NotTokenOwner);
+		assert_ok!(Marketplace::reject_sale(Origin::signed(ALICE), token_id));
+		System::assert_last_event(MarketplaceEvent::PendingSaleRefunded(token_id, BOB, 100).into());
+
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert!(Marketplace::get_pending_sale(token_id).is_none());
+		assert_eq!(owner_of(token_id), Some(ALICE));
+		assert!(Marketplace::is_onsale(token_id).is_some());
+
+		assert_ok!(Marketplace::buy(Origin::signed(CHARLIE), token_id));
+		assert_eq!(Balances::reserved_balance(CHARLIE), 100);
+	});
+}
+
+#[test]
+fn halted_token_cannot_trade_until_the_halt_passes() {
+	new_test_ext().execute_with(|| {
+		let listed = mint_and_list(ALICE, 100);
+		let unlisted = mint(ALICE);
+
+		assert_noop!(Marketplace::halt_token(Origin::signed(ALICE), listed, 5), DispatchError::BadOrigin);
+		assert_ok!(Marketplace::halt_token(Origin::root(), listed, 5));
+		System::assert_last_event(MarketplaceEvent::TokenTradingHalted(listed, 5).into());
+		assert_ok!(Marketplace::halt_token(Origin::root(), unlisted, 5));
+
+		assert_noop!(Marketplace::buy(Origin::signed(BOB), listed), Error::<Test>::TokenTradingHalted);
+		assert_noop!(Marketplace::sell(Origin::signed(ALICE), unlisted, 100), Error::<Test>::TokenTradingHalted);
+		assert_noop!(
+			Marketplace::start_sealed_auction(Origin::signed(ALICE), unlisted, 10, 5, 5),
+			Error::<Test>::TokenTradingHalted
+		);
+
+		run_to_block(6);
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), listed));
+		assert_eq!(owner_of(listed), Some(BOB));
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), unlisted, 100));
+	});
+}
+
+#[test]
+fn halted_token_cannot_complete_an_installment_plan() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), token_id, BOB, 200, 2, 10));
+		assert_ok!(Marketplace::halt_token(Origin::root(), token_id, 30));
+
+		// Installments that don't move the token can still be paid, the last one waits
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		run_to_block(21);
+		assert_noop!(Marketplace::pay_installment(Origin::signed(BOB), 0), Error::<Test>::TokenTradingHalted);
+		assert_eq!(Balances::reserved_balance(BOB), 100);
+
+		// A block in the past lifts the halt
+		assert_ok!(Marketplace::halt_token(Origin::root(), token_id, 0));
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		assert_eq!(owner_of(token_id), Some(BOB));
+	});
+}
+
+#[test]
+fn halt_over_the_due_date_defers_the_installment_default() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), token_id, BOB, 200, 2, 10));
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		assert_ok!(Marketplace::halt_token(Origin::root(), token_id, 30));
+
+		// The last installment fell due during the halt, the grace period starts after it
+		run_to_block(24);
+		assert_noop!(
+			Marketplace::claim_installment_default(Origin::signed(ALICE), 0),
+			Error::<Test>::InstallmentNotOverdue
+		);
+		run_to_block(32);
+		assert_noop!(
+			Marketplace::claim_installment_default(Origin::signed(ALICE), 0),
+			Error::<Test>::InstallmentNotOverdue
+		);
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		assert_eq!(owner_of(token_id), Some(BOB));
+	});
+}
+
+#[test]
+fn block_volume_cap_defers_sales_to_the_next_block() {
+	new_test_ext().execute_with(|| {
+		MaxVolumePerBlock::set(250);
+		let tokens: Vec<u64> = (0..3).map(|_| mint_and_list(ALICE, 100)).collect();
+
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), tokens[0]));
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), tokens[1]));
+		assert_eq!(Marketplace::get_block_volume(), 200);
+		assert_noop!(Marketplace::buy(Origin::signed(BOB), tokens[2]), Error::<Test>::BlockVolumeCapReached);
+
+		run_to_block(2);
+		assert_eq!(Marketplace::get_block_volume(), 0);
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), tokens[2]));
+		assert_eq!(owner_of(tokens[2]), Some(BOB));
+	});
+}
+
+#[test]
+fn block_volume_cap_covers_completing_installments() {
+	new_test_ext().execute_with(|| {
+		MaxVolumePerBlock::set(250);
+		let listed = mint_and_list(ALICE, 100);
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), token_id, BOB, 200, 2, 10));
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+
+		assert_ok!(Marketplace::buy(Origin::signed(CHARLIE), listed));
+		assert_noop!(Marketplace::pay_installment(Origin::signed(BOB), 0), Error::<Test>::BlockVolumeCapReached);
+
+		run_to_block(2);
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		assert_eq!(Marketplace::get_block_volume(), 200);
+		assert_eq!(owner_of(token_id), Some(BOB));
+	});
+}
+
+#[test]
+fn sale_proceeds_go_to_the_proceeds_recipient() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_ok!(Marketplace::set_listing_manager(Origin::signed(ALICE), BOB, true));
+		assert_noop!(
+			Marketplace::set_proceeds_recipient(Origin::signed(BOB), token_id, Some(BOB)),
+			Error::<Test>::NotTokenOwner
+		);
+
+		assert_ok!(Marketplace::set_proceeds_recipient(Origin::signed(ALICE), token_id, Some(CHARLIE)));
+		System::assert_last_event(MarketplaceEvent::ProceedsRecipientSet(token_id, Some(CHARLIE)).into());
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), token_id, 100));
+		assert_ok!(Marketplace::buy(Origin::signed(DAVE), token_id));
+
+		assert_eq!(Balances::free_balance(CHARLIE), INITIAL_BALANCE + 100);
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE);
+		assert_eq!(owner_of(token_id), Some(DAVE));
+		// The routing was the previous owner's choice
+		assert_eq!(Marketplace::get_proceeds_recipient(token_id), None);
+	});
+}
+
+#[test]
+fn sale_refunds_the_unused_boost() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_listing_manager(Origin::signed(ALICE), BOB, true));
+		assert_noop!(Marketplace::boost_listing(Origin::signed(BOB), token_id, 10), Error::<Test>::NotTokenOwner);
+
+		// Boosted until block 11 for 100
+		assert_ok!(Marketplace::boost_listing(Origin::signed(ALICE), token_id, 10));
+
+		run_to_block(5);
+		assert_ok!(Marketplace::buy(Origin::signed(CHARLIE), token_id));
+		System::assert_has_event(MarketplaceEvent::BoostRefunded(token_id, ALICE, 60).into());
+
+		assert_eq!(Balances::free_balance(ALICE), INITIAL_BALANCE - 100 + 60 + 100);
+		assert_eq!(Balances::free_balance(FeeAccount::get()), INITIAL_BALANCE + 100 - 60);
+	});
+}
+
+#[test]
+fn owners_of_and_exist_answer_positionally() {
+	new_test_ext().execute_with(|| {
+		let alices = mint(ALICE);
+		let bobs = mint(BOB);
+
+		assert_eq!(Marketplace::owners_of(vec![bobs, 5, alices]), vec![Some(BOB), None, Some(ALICE)]);
+		assert_eq!(Marketplace::exist(vec![bobs, 5, alices]), vec![true, false, true]);
+		assert_eq!(Marketplace::exist(vec![alices; 7]).len(), 5);
+	});
+}
+
+#[test]
+fn leaderboards_rank_collectors_and_creators() {
+	new_test_ext().execute_with(|| {
+		let alices: Vec<u64> = (0..4).map(|_| mint(ALICE)).collect();
+		mint(BOB);
+		mint(CHARLIE);
+		mint(CHARLIE);
+
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), alices[0], 100));
+		assert_ok!(Marketplace::buy(Origin::signed(DAVE), alices[0]));
+
+		assert_eq!(Marketplace::top_collectors(2), vec![(ALICE, 3), (CHARLIE, 2)]);
+		assert_eq!(Marketplace::top_creators(10), vec![(ALICE, 4), (CHARLIE, 2), (BOB, 1)]);
+	});
+}
+
+#[test]
+fn relisting_within_the_window_must_move_the_price() {
+	new_test_ext().execute_with(|| {
+		MinRelistPriceDelta::set(10);
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::cancel_order(Origin::signed(ALICE), token_id));
+
+		assert_noop!(Marketplace::sell(Origin::signed(ALICE), token_id, 105), Error::<Test>::RelistPriceTooSimilar);
+		assert_noop!(Marketplace::sell(Origin::signed(ALICE), token_id, 95), Error::<Test>::RelistPriceTooSimilar);
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), token_id, 110));
+
+		// Outside the window any price goes
+		assert_ok!(Marketplace::cancel_order(Origin::signed(ALICE), token_id));
+		run_to_block(12);
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), token_id, 111));
+	});
+}
+
+#[test]
+fn root_releases_an_accounts_bids_and_pending_purchases() {
+	new_test_ext().execute_with(|| {
+		let first = mint(ALICE);
+		let second = mint(ALICE);
+		// Commits until block 3, reveals until block 8
+		assert_ok!(Marketplace::start_sealed_auction(Origin::signed(ALICE), first, 10, 2, 5));
+		assert_ok!(Marketplace::start_sealed_auction(Origin::signed(ALICE), second, 10, 2, 5));
+		let pending = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_require_confirmation(Origin::signed(ALICE), pending, true));
+
+		assert_ok!(Marketplace::commit_bid(Origin::signed(BOB), 0, bid_hash(BOB, 100, SALT), 150));
+		assert_ok!(Marketplace::commit_bid(Origin::signed(BOB), 1, bid_hash(BOB, 50, SALT), 60));
+		assert_ok!(Marketplace::commit_bid(Origin::signed(CHARLIE), 0, bid_hash(CHARLIE, 80, SALT), 80));
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), pending));
+		assert_eq!(Balances::reserved_balance(BOB), 310);
+
+		run_to_block(4);
+		assert_ok!(Marketplace::reveal_bid(Origin::signed(BOB), 0, 100, SALT));
+
+		assert_noop!(
+			Marketplace::release_account_reserves(Origin::root(), BOB, 2, 1, 0),
+			Error::<Test>::WitnessTooLow
+		);
+		assert_noop!(
+			Marketplace::release_account_reserves(Origin::root(), BOB, 3, 0, 0),
+			Error::<Test>::WitnessTooLow
+		);
+		assert_ok!(Marketplace::release_account_reserves(Origin::root(), BOB, 3, 1, 0));
+		System::assert_last_event(MarketplaceEvent::AccountReservesReleased(BOB, 3, 310).into());
+
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE);
+		assert_eq!(Balances::reserved_balance(CHARLIE), 80);
+		assert!(Marketplace::get_bid_commitment(0, BOB).is_none());
+		assert!(Marketplace::get_bid_commitment(0, CHARLIE).is_some());
+		assert!(Marketplace::get_pending_sale(pending).is_none());
+
+		let auction = Marketplace::get_sealed_auction(0).unwrap();
+		assert_eq!(auction.bid_count, 1);
+		assert_eq!(auction.best_bid, None);
+		assert_eq!(Marketplace::get_sealed_auction(1).unwrap().bid_count, 0);
+	});
+}
+
+#[test]
+fn root_refunds_the_installment_plans_an_account_is_buying() {
+	new_test_ext().execute_with(|| {
+		let bobs_plan = mint(ALICE);
+		let charlies_plan = mint(ALICE);
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), bobs_plan, BOB, 200, 2, 10));
+		assert_ok!(Marketplace::start_installment_plan(Origin::signed(ALICE), charlies_plan, CHARLIE, 200, 2, 10));
+		assert_ok!(Marketplace::pay_installment(Origin::signed(BOB), 0));
+		assert_ok!(Marketplace::pay_installment(Origin::signed(CHARLIE), 1));
+
+		assert_noop!(
+			Marketplace::release_account_reserves(Origin::root(), BOB, 0, 0, 1),
+			Error::<Test>::WitnessTooLow
+		);
+		assert_ok!(Marketplace::release_account_reserves(Origin::root(), BOB, 0, 0, 2));
+		System::assert_has_event(MarketplaceEvent::InstallmentPlanDefaulted(0, BOB, 100, false).into());
+		System::assert_last_event(MarketplaceEvent::AccountReservesReleased(BOB, 1, 100).into());
+
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert_eq!(Balances::free_balance(BOB), INITIAL_BALANCE);
+		assert!(Marketplace::get_installment_plan(0).is_none());
+		assert_eq!(owner_of(bobs_plan), Some(ALICE));
+		assert_eq!(Balances::reserved_balance(CHARLIE), 100);
+		assert!(Marketplace::get_installment_plan(1).is_some());
+
+		// The token is free to sell again
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), bobs_plan, 100));
+	});
+}
+
+#[test]
+fn import_tokens_keeps_the_indexes_consistent() {
+	new_test_ext().execute_with(|| {
+		mint(ALICE);
+
+		assert_noop!(
+			Marketplace::import_tokens(Origin::signed(ALICE), vec![(10, BOB)]),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(Marketplace::import_tokens(Origin::root(), vec![(0, BOB)]), Error::<Test>::TokenAlreadyExists);
+		assert_noop!(
+			Marketplace::import_tokens(Origin::root(), (20..26).map(|id| (id, BOB)).collect()),
+			Error::<Test>::BatchTooLarge
+		);
+
+		assert_ok!(Marketplace::import_tokens(Origin::root(), vec![(10, BOB), (3, CHARLIE), (11, BOB)]));
+		System::assert_last_event(MarketplaceEvent::TokensImported(3).into());
+
+		assert_eq!(Marketplace::get_next_token_id(), Some(12));
+		assert_eq!(Marketplace::get_number_of_nfts_owned(BOB), Some(2));
+		assert_eq!(Marketplace::get_token_ids_of_owned_nfts(BOB, 0), Some(10));
+		assert_eq!(Marketplace::get_token_ids_of_owned_nfts(BOB, 1), Some(11));
+		assert_eq!(Marketplace::get_nft_details(11), Some((BOB, 1)));
+		assert_eq!(Marketplace::get_nft_details(3), Some((CHARLIE, 0)));
+
+		assert_eq!(mint(ALICE), 12);
+	});
+}
+
+#[test]
+fn restricted_listing_only_sells_to_allowlisted_buyers() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint_and_list(ALICE, 100);
+		assert_ok!(Marketplace::set_restrict_buyers(Origin::signed(ALICE), token_id, true));
+
+		for buyer in [BOB, DAVE, 10, 11] {
+			assert_ok!(Marketplace::add_listing_buyer(Origin::signed(ALICE), token_id, buyer));
+		}
+		assert_noop!(
+			Marketplace::add_listing_buyer(Origin::signed(ALICE), token_id, 12),
+			Error::<Test>::TooManyListingBuyers
+		);
+		// Adding a buyer twice doesn't take another slot
+		assert_ok!(Marketplace::add_listing_buyer(Origin::signed(ALICE), token_id, BOB));
+		assert_ok!(Marketplace::remove_listing_buyer(Origin::signed(ALICE), token_id, 11));
+		assert_eq!(Marketplace::get_listing_buyer_count(token_id), 3);
+
+		assert_noop!(Marketplace::buy(Origin::signed(CHARLIE), token_id), Error::<Test>::BuyerNotAllowed);
+		assert_ok!(Marketplace::buy(Origin::signed(BOB), token_id));
+		assert_eq!(owner_of(token_id), Some(BOB));
+
+		// The allowlist was the previous owner's
+		assert!(Marketplace::get_listing_buyer(token_id, DAVE).is_none());
+		assert_eq!(Marketplace::get_listing_buyer_count(token_id), 0);
+	});
+}
+
+#[test]
+fn reports_halt_the_token_and_outlive_a_relisting() {
+	new_test_ext().execute_with(|| {
+		let token_id = mint(ALICE);
+		assert_noop!(Marketplace::report_listing(Origin::signed(BOB), token_id, 1), Error::<Test>::TokenNotOnSale);
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), token_id, 100));
+
+		assert_ok!(Marketplace::report_listing(Origin::signed(BOB), token_id, 1));
+		System::assert_last_event(MarketplaceEvent::ListingReported(token_id, BOB, 1).into());
+		assert_noop!(Marketplace::report_listing(Origin::signed(BOB), token_id, 2), Error::<Test>::AlreadyReported);
+		assert_ok!(Marketplace::report_listing(Origin::signed(CHARLIE), token_id, 1));
+		assert_eq!(Marketplace::get_report_count(token_id), 2);
+		assert_eq!(Marketplace::get_halted_until(token_id), None);
+
+		assert_ok!(Marketplace::report_listing(Origin::signed(DAVE), token_id, 3));
+		System::assert_last_event(MarketplaceEvent::TokenTradingHalted(token_id, 11).into());
+		assert_noop!(Marketplace::buy(Origin::signed(BOB), token_id), Error::<Test>::TokenTradingHalted);
+		assert_eq!(Marketplace::get_report_count(token_id), 0);
+		assert!(Marketplace::get_reporter(token_id, BOB).is_none());
+
+		// Reports can't stretch a running halt
+		assert_noop!(Marketplace::report_listing(Origin::signed(BOB), token_id, 1), Error::<Test>::TokenTradingHalted);
+
+		// Reports survive relisting
+		run_to_block(12);
+		assert_ok!(Marketplace::report_listing(Origin::signed(BOB), token_id, 1));
+		assert_ok!(Marketplace::cancel_order(Origin::signed(ALICE), token_id));
+		assert_ok!(Marketplace::sell(Origin::signed(ALICE), token_id, 100));
+		assert_eq!(Marketplace::get_report_count(token_id), 1);
+		assert_noop!(Marketplace::report_listing(Origin::signed(BOB), token_id, 1), Error::<Test>::AlreadyReported);
+		for reporter in [CHARLIE, DAVE] {
+			assert_ok!(Marketplace::report_listing(Origin::signed(reporter), token_id, 1));
+		}
+		assert_eq!(Marketplace::get_halted_until(token_id), Some(22));
+
+		// They end with the owner they were made against
+		run_to_block(23);
+		assert_ok!(Marketplace::report_listing(Origin::signed(BOB), token_id, 1));
+		assert_ok!(Marketplace::buy(Origin::signed(CHARLIE), token_id));
+		assert_eq!(Marketplace::get_report_count(token_id), 0);
+		assert!(Marketplace::get_reporter(token_id, BOB).is_none());
+	});
+}
+
+#[test]
+fn minted_tokens_get_distinct_deterministic_seeds() {
+	new_test_ext().execute_with(|| {
+		let first = mint(ALICE);
+		let second = mint(ALICE);
+
+		let first_seed = Marketplace::get_token_seed(first).unwrap();
+		let second_seed = Marketplace::get_token_seed(second).unwrap();
+		assert_ne!(first_seed, second_seed);
+		assert_eq!(first_seed, BlakeTwo256::hash(&(b"nft/seed", first).encode()));
+		assert_eq!(second_seed, BlakeTwo256::hash(&(b"nft/seed", second).encode()));
+	});
+}
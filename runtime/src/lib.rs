@@ -14,7 +14,10 @@ use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
+	traits::{
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount,
+		NumberFor, Verify,
+	},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
@@ -25,9 +28,10 @@ use sp_version::RuntimeVersion;
 
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
-	construct_runtime, parameter_types,
+	construct_runtime, parameter_types, PalletId,
 	traits::{
-		ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo,
+		ConstBool, ConstU128, ConstU32, ConstU64, ConstU8, KeyOwnerProofSystem, Randomness,
+		StorageInfo,
 	},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
@@ -101,7 +105,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -261,10 +265,35 @@ impl pallet_sudo::Config for Runtime {
 	type Call = Call;
 }
 
+parameter_types! {
+	pub MarketplaceFeeAccount: AccountId = PalletId(*b"py/nftmk").into_account();
+	pub MarketplaceNullAccount: AccountId = PalletId(*b"py/nftnl").into_account();
+}
+
 /// Configure the pallet-template in pallets/template.
 impl pallet_marketplace::Config for Runtime {
 	type Event = Event;
 	type Currency = Balances;
+	type MaxSealedBids = ConstU32<100>;
+	type InstallmentGracePeriod = ConstU32<DAYS>;
+	type ForfeitInstallmentsOnDefault = ConstBool<true>;
+	type MaxBatchSize = ConstU32<50>;
+	type FeeAccount = MarketplaceFeeAccount;
+	type BoostFee = ConstU128<1_000>;
+	type MinAccountAgeToSell = ConstU32<0>;
+	type NullAccount = MarketplaceNullAccount;
+	type BurnRelinquished = ConstBool<false>;
+	type MaxRecentSales = ConstU32<20>;
+	type SaleConfirmationTimeout = ConstU32<DAYS>;
+	type MaxVolumePerBlock = ConstU128<0>;
+	type RelistWindow = ConstU32<0>;
+	type MinRelistPriceDelta = ConstU128<0>;
+	type ReportThreshold = ConstU32<10>;
+	type ReportHaltDuration = ConstU32<DAYS>;
+	type Randomness = RandomnessCollectiveFlip;
+	type MaxBuyCommitments = ConstU32<32>;
+	type MaxListingBuyers = ConstU32<100>;
+	type BuyCommitmentLifetime = ConstU32<HOURS>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -463,6 +492,50 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_marketplace_runtime_api::MarketplaceApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+		fn listing_age(token_id: u64) -> Option<BlockNumber> {
+			Marketplace::listing_age(token_id)
+		}
+
+		fn stale_listings(threshold: BlockNumber) -> Vec<u64> {
+			Marketplace::stale_listings(threshold)
+		}
+
+		fn is_verified_creator(account: AccountId) -> bool {
+			Marketplace::is_verified_creator(account)
+		}
+
+		fn featured_listings() -> Vec<(u64, BlockNumber)> {
+			Marketplace::featured_listings()
+		}
+
+		fn batch_token_query(
+			token_ids: Vec<u64>,
+		) -> Vec<pallet_marketplace_runtime_api::TokenView<AccountId, Balance>> {
+			Marketplace::batch_token_query(token_ids)
+		}
+
+		fn recent_sales() -> Vec<(u64, AccountId, AccountId, Balance, BlockNumber)> {
+			Marketplace::recent_sales()
+		}
+
+		fn owners_of(token_ids: Vec<u64>) -> Vec<Option<AccountId>> {
+			Marketplace::owners_of(token_ids)
+		}
+
+		fn exist(token_ids: Vec<u64>) -> Vec<bool> {
+			Marketplace::exist(token_ids)
+		}
+
+		fn top_collectors(limit: u32) -> Vec<(AccountId, u64)> {
+			Marketplace::top_collectors(limit)
+		}
+
+		fn top_creators(limit: u32) -> Vec<(AccountId, u64)> {
+			Marketplace::top_creators(limit)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (